@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+
+use super::wine;
+use super::dxvk;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateAdvice {
+    /// Newer recommended wine version is available within the selected version's group
+    WineUpdateAvailable {
+        current: wine::Version,
+        latest: wine::Version
+    },
+
+    /// Selected wine version is not recommended, but there's a recommended one in another group
+    WineRecommendedAvailable {
+        current: wine::Version,
+        recommended: wine::Version
+    },
+
+    /// Selected wine version is not listed in the components index anymore
+    WineDropped {
+        current: String
+    },
+
+    /// Newer recommended dxvk version is available within the installed version's group
+    DxvkUpdateAvailable {
+        current: dxvk::Version,
+        latest: dxvk::Version
+    },
+
+    /// Installed dxvk version is not recommended, but there's a recommended one in another group
+    DxvkRecommendedAvailable {
+        current: dxvk::Version,
+        recommended: dxvk::Version
+    },
+
+    /// Installed dxvk version is not listed in the components index anymore
+    DxvkDropped {
+        current: String
+    }
+}
+
+enum Advice<T> {
+    UpToDate,
+    Update(T, T),
+    Recommended(T, T),
+    Dropped
+}
+
+/// Get numbers from the version string to compare versions from different groups
+///
+/// `wine-9.0-staging-tkg-amd64` -> `[9, 0, 64]`, `lutris-GE-Proton8-26-x86_64` -> `[8, 26, 86, 64]`
+fn version_numbers(version: &str) -> Vec<u64> {
+    version.split(|char: char| !char.is_ascii_digit())
+        .filter_map(|number| number.parse().ok())
+        .collect()
+}
+
+/// Find advice for the component in given groups
+///
+/// Every group is a list of versions sorted from the newest to the oldest
+/// with their `recommended` feature flag. Versions from different groups
+/// are compared by the numbers of their `version` strings
+fn advise_in<T: Clone>(groups: Vec<Vec<(T, bool)>>, is_current: impl Fn(&T) -> bool, version: impl Fn(&T) -> &str) -> Advice<T> {
+    let recommended = groups.iter()
+        .flat_map(|versions| versions.iter().find(|(_, recommended)| *recommended))
+        .map(|(version, _)| version.clone())
+        .next();
+
+    for versions in &groups {
+        let Some(position) = versions.iter().position(|(version, _)| is_current(version)) else {
+            continue;
+        };
+
+        let (current, current_recommended) = versions[position].clone();
+
+        // Versions are sorted from newest to oldest so every recommended
+        // version before the current one is its update
+        if let Some((latest, _)) = versions[..position].iter().find(|(_, recommended)| *recommended) {
+            return Advice::Update(current, latest.clone());
+        }
+
+        if !current_recommended {
+            if let Some(recommended) = recommended {
+                // Recommended build from another group can be older than the current one
+                let newer = version_numbers(version(&recommended)) > version_numbers(version(&current));
+
+                if !is_current(&recommended) && newer {
+                    return Advice::Recommended(current, recommended);
+                }
+            }
+        }
+
+        return Advice::UpToDate;
+    }
+
+    Advice::Dropped
+}
+
+/// Get update advice for the selected wine version
+pub fn advise_wine(components: impl Into<PathBuf>, selected: impl AsRef<str>) -> anyhow::Result<Option<UpdateAdvice>> {
+    let components: PathBuf = components.into();
+    let selected = selected.as_ref();
+
+    let groups = wine::get_groups(&components)?
+        .into_iter()
+        .map(|group| {
+            group.versions.iter()
+                .map(|version| {
+                    let recommended = version.features_in(&group)
+                        .unwrap_or_default()
+                        .recommended;
+
+                    (version.clone(), recommended)
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(match advise_in(groups, |version: &wine::Version| version.name == selected, |version| &version.name) {
+        Advice::UpToDate => None,
+
+        Advice::Update(current, latest) => Some(UpdateAdvice::WineUpdateAvailable { current, latest }),
+        Advice::Recommended(current, recommended) => Some(UpdateAdvice::WineRecommendedAvailable { current, recommended }),
+
+        Advice::Dropped => Some(UpdateAdvice::WineDropped {
+            current: selected.to_string()
+        })
+    })
+}
+
+/// Get update advice for the installed dxvk version
+///
+/// `installed` can be either dxvk version's name or its version
+pub fn advise_dxvk(components: impl Into<PathBuf>, installed: impl AsRef<str>) -> anyhow::Result<Option<UpdateAdvice>> {
+    let components: PathBuf = components.into();
+    let installed = installed.as_ref();
+
+    let groups = dxvk::get_groups(&components)?
+        .into_iter()
+        .map(|group| {
+            group.versions.iter()
+                .map(|version| {
+                    let recommended = version.features_in(&group)
+                        .unwrap_or_default()
+                        .recommended;
+
+                    (version.clone(), recommended)
+                })
+                .collect()
+        })
+        .collect();
+
+    let is_installed = |version: &dxvk::Version| version.name == installed || version.version == installed;
+
+    Ok(match advise_in(groups, is_installed, |version| &version.version) {
+        Advice::UpToDate => None,
+
+        Advice::Update(current, latest) => Some(UpdateAdvice::DxvkUpdateAvailable { current, latest }),
+        Advice::Recommended(current, recommended) => Some(UpdateAdvice::DxvkRecommendedAvailable { current, recommended }),
+
+        Advice::Dropped => Some(UpdateAdvice::DxvkDropped {
+            current: installed.to_string()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advise(groups: Vec<Vec<(&'static str, bool)>>, current: &str) -> Advice<&'static str> {
+        advise_in(groups, |version| *version == current, |version| version)
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(version_numbers("wine-9.0-staging-tkg-amd64"), [9, 0, 64]);
+        assert_eq!(version_numbers("lutris-GE-Proton8-26-x86_64"), [8, 26, 86, 64]);
+        assert_eq!(version_numbers("dxvk"), Vec::<u64>::new());
+
+        assert!(version_numbers("2.10") > version_numbers("2.9"));
+        assert!(version_numbers("9.0.1") > version_numbers("9.0"));
+    }
+
+    #[test]
+    fn advice() {
+        let groups = || vec![
+            vec![("wine-9.2", false), ("wine-9.1", true), ("wine-9.0", false)],
+            vec![("proton-9.5", false)]
+        ];
+
+        assert!(matches!(advise(groups(), "wine-9.0"), Advice::Update("wine-9.0", "wine-9.1")));
+        assert!(matches!(advise(groups(), "wine-9.1"), Advice::UpToDate));
+        assert!(matches!(advise(groups(), "wine-8.0"), Advice::Dropped));
+
+        // Recommended build of another group is suggested only if it's newer
+        assert!(matches!(advise(groups(), "proton-9.5"), Advice::UpToDate));
+
+        let groups = vec![
+            vec![("wine-9.1", true)],
+            vec![("proton-8.0", false)]
+        ];
+
+        assert!(matches!(advise(groups, "proton-8.0"), Advice::Recommended("proton-8.0", "wine-9.1")));
+    }
+}
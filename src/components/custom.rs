@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use wincompatlib::prelude::*;
+
+use super::wine;
+
+/// Known wine builds layouts
+///
+/// Every entry is a folder relative to the build's root
+/// which contains wine binaries
+const WINE_LAYOUTS: &[&str] = &[
+    "bin",
+    "files/bin",
+    "dist/bin"
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomRunner {
+    /// Unique runner name which is stored in the `game.wine.selected` config field
+    pub name: String,
+
+    /// Human readable runner name
+    pub title: String,
+
+    /// Path to the runner's folder
+    ///
+    /// Relative paths are resolved from the wine builds folder
+    pub path: PathBuf,
+
+    /// Paths to the wine binaries relative to the runner's folder
+    pub files: wine::Files,
+
+    /// Runner features. If not specified, then default values are used
+    pub features: Option<wine::Features>
+}
+
+impl From<&JsonValue> for CustomRunner {
+    fn from(value: &JsonValue) -> Self {
+        let name = value.get("name")
+            .and_then(JsonValue::as_str)
+            .map(String::from)
+            .unwrap_or_default();
+
+        Self {
+            title: value.get("title")
+                .and_then(JsonValue::as_str)
+                .map(String::from)
+                .unwrap_or_else(|| name.clone()),
+
+            path: value.get("path")
+                .and_then(JsonValue::as_str)
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(&name)),
+
+            files: value.get("files")
+                .and_then(|files| serde_json::from_value(files.clone()).ok())
+                .unwrap_or_else(|| wine::Files {
+                    wine: String::from("bin/wine"),
+                    wine64: Some(String::from("bin/wine64")),
+                    wineserver: Some(String::from("bin/wineserver")),
+                    wineboot: Some(String::from("bin/wineboot"))
+                }),
+
+            features: value.get("features")
+                .and_then(|features| {
+                    if features.is_null() {
+                        None
+                    } else {
+                        Some(wine::Features::from(features))
+                    }
+                }),
+
+            name
+        }
+    }
+}
+
+impl CustomRunner {
+    /// Try to detect wine build in given folder
+    ///
+    /// Returns `None` if there's no known wine layout in it
+    pub fn detect(folder: impl AsRef<Path>) -> Option<Self> {
+        let folder = folder.as_ref();
+
+        let name = folder.file_name()?.to_string_lossy().to_string();
+
+        for layout in WINE_LAYOUTS {
+            let bin = folder.join(layout);
+
+            let wine64 = bin.join("wine64").exists();
+            let wine = bin.join("wine").exists();
+
+            if !wine && !wine64 {
+                continue;
+            }
+
+            let file = |name: &str| {
+                if bin.join(name).exists() {
+                    Some(format!("{layout}/{name}"))
+                } else {
+                    None
+                }
+            };
+
+            // Wine 9+ builds ship a single `wine` binary which handles both
+            // architectures (new WoW64 mode) so we assume they're 64 bit
+            let arch = if wine64 || folder.join("lib/wine/x86_64-unix").exists() {
+                WineArch::Win64
+            } else {
+                WineArch::Win32
+            };
+
+            return Some(Self {
+                title: name.clone(),
+                path: folder.to_path_buf(),
+
+                files: wine::Files {
+                    wine: file("wine").unwrap_or_else(|| format!("{layout}/wine64")),
+                    wine64: file("wine64"),
+                    wineserver: file("wineserver"),
+                    wineboot: file("wineboot")
+                },
+
+                features: Some(wine::Features {
+                    arch: Some(arch),
+                    recommended: false,
+                    ..wine::Features::default()
+                }),
+
+                name
+            });
+        }
+
+        None
+    }
+
+    #[inline]
+    /// Get absolute path to the runner's folder
+    pub fn folder(&self, builds: impl AsRef<Path>) -> PathBuf {
+        builds.as_ref().join(&self.path)
+    }
+
+    /// Convert custom runner to the components index wine version
+    pub fn to_version(&self) -> wine::Version {
+        wine::Version {
+            name: self.name.clone(),
+            title: self.title.clone(),
+            uri: String::new(),
+            format: None,
+            files: self.files.clone(),
+            features: self.features.clone()
+        }
+    }
+}
+
+/// Read custom runners manifest
+///
+/// Returns empty list if the manifest doesn't exist
+pub fn get_manifest(manifest: impl AsRef<Path>) -> anyhow::Result<Vec<CustomRunner>> {
+    let manifest = manifest.as_ref();
+
+    if !manifest.exists() {
+        return Ok(Vec::new());
+    }
+
+    let runners = serde_json::from_slice::<JsonValue>(&std::fs::read(manifest)?)?;
+
+    match runners.as_array() {
+        Some(runners) => Ok(runners.iter()
+            .map(CustomRunner::from)
+            .filter(|runner| !runner.name.is_empty())
+            .collect()),
+
+        None => anyhow::bail!("Wrong custom runners manifest structure: it must be a list")
+    }
+}
+
+/// Write custom runners manifest
+pub fn set_manifest(manifest: impl AsRef<Path>, runners: &[CustomRunner]) -> anyhow::Result<()> {
+    Ok(std::fs::write(manifest, serde_json::to_string_pretty(runners)?)?)
+}
+
+/// Detect wine builds in given folder which are not listed in the components index
+pub fn detect_in(components: impl Into<PathBuf>, builds: impl AsRef<Path>) -> anyhow::Result<Vec<CustomRunner>> {
+    let builds = builds.as_ref();
+
+    if !builds.exists() {
+        return Ok(Vec::new());
+    }
+
+    let indexed = wine::get_groups(components)?
+        .into_iter()
+        .flat_map(|group| group.versions)
+        .map(|version| version.name)
+        .collect::<Vec<_>>();
+
+    let mut runners = Vec::new();
+
+    for entry in std::fs::read_dir(builds)?.flatten() {
+        let path = entry.path();
+
+        if !path.is_dir() || indexed.iter().any(|name| path.ends_with(name)) {
+            continue;
+        }
+
+        if let Some(runner) = CustomRunner::detect(path) {
+            runners.push(runner);
+        }
+    }
+
+    runners.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(runners)
+}
+
+/// List custom runners from the manifest merged with the ones
+/// automatically detected in the wine builds folder
+///
+/// Manifest entries take precedence over detected ones
+pub fn get_custom(components: impl Into<PathBuf>, manifest: impl AsRef<Path>, builds: impl AsRef<Path>) -> anyhow::Result<Vec<CustomRunner>> {
+    let mut runners = get_manifest(manifest)?;
+
+    for runner in detect_in(components, builds)? {
+        if !runners.iter().any(|custom| custom.name == runner.name) {
+            runners.push(runner);
+        }
+    }
+
+    Ok(runners)
+}
+
+/// Find custom runner with given name
+pub fn find_in(components: impl Into<PathBuf>, manifest: impl AsRef<Path>, builds: impl AsRef<Path>, name: impl AsRef<str>) -> anyhow::Result<Option<CustomRunner>> {
+    let name = name.as_ref();
+
+    Ok(get_custom(components, manifest, builds)?
+        .into_iter()
+        .find(|runner| runner.name == name))
+}
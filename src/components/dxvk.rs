@@ -175,11 +175,11 @@ impl Version {
     pub fn install<T: Into<PathBuf> + std::fmt::Debug>(&self, dxvks_folder: T, wine: &Wine, params: InstallParams) -> anyhow::Result<()> {
         tracing::debug!("Installing DXVK");
 
-        Dxvk::install(
-            wine,
-            dxvks_folder.into().join(&self.name),
-            params
-        )
+        let folder = dxvks_folder.into().join(&self.name);
+
+        Dxvk::install(wine, &folder, params)?;
+
+        super::gc::mark_used(folder)
     }
 
     #[inline]
@@ -0,0 +1,238 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Serialize, Deserialize};
+
+use super::dxvk;
+use super::vkd3d;
+
+/// File which modification time stores component's last usage time
+const LAST_USED_FILE: &str = ".last_used";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ComponentKind {
+    Wine,
+    Dxvk,
+    Vkd3d
+}
+
+/// Components used by some game config or profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentsUsage {
+    /// Path to the components index
+    pub components: PathBuf,
+
+    pub wine_builds: PathBuf,
+    pub dxvk_builds: PathBuf,
+    pub vkd3d_builds: PathBuf,
+
+    /// Name of the selected wine build
+    pub selected_wine: Option<String>,
+
+    /// Path to the wine prefix
+    pub prefix: PathBuf
+}
+
+impl ComponentsUsage {
+    /// Check if given component is used by this config
+    pub fn is_referenced(&self, kind: ComponentKind, name: &str) -> bool {
+        match kind {
+            ComponentKind::Wine => self.selected_wine.as_deref() == Some(name),
+            ComponentKind::Dxvk | ComponentKind::Vkd3d => self.is_installed(kind, name)
+        }
+    }
+
+    /// Check if given component is installed in the wine prefix
+    pub fn is_installed(&self, kind: ComponentKind, name: &str) -> bool {
+        match kind {
+            ComponentKind::Wine => false,
+
+            ComponentKind::Dxvk => {
+                let Ok(Some(version)) = wincompatlib::dxvk::Dxvk::get_version(&self.prefix) else {
+                    return false;
+                };
+
+                match dxvk::Version::find_in(&self.components, &version) {
+                    Ok(Some(dxvk)) => dxvk.name == name,
+
+                    // Fallback to the version comparison if the index is not available
+                    _ => name.ends_with(&version)
+                }
+            }
+
+            ComponentKind::Vkd3d => matches!(vkd3d::get_version(&self.prefix), Ok(Some(version)) if version == name)
+        }
+    }
+
+    /// Get builds folder of given components kind
+    pub fn builds(&self, kind: ComponentKind) -> &Path {
+        match kind {
+            ComponentKind::Wine => &self.wine_builds,
+            ComponentKind::Dxvk => &self.dxvk_builds,
+            ComponentKind::Vkd3d => &self.vkd3d_builds
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadedComponent {
+    pub kind: ComponentKind,
+    pub name: String,
+    pub path: PathBuf,
+
+    /// Size of the component's folder in bytes
+    pub size: u64,
+
+    /// Last time the component was used. `None` if it was never used
+    /// since the usage tracking was introduced
+    pub last_used: Option<SystemTime>,
+
+    /// Component is referenced by some game config or profile
+    pub referenced: bool,
+
+    /// Component is the selected wine build or dxvk installed
+    /// in some prefix, so it can't be removed
+    pub protected: bool
+}
+
+/// Get size of the file or folder without following symlinks
+pub fn get_size(path: impl AsRef<Path>) -> std::io::Result<u64> {
+    let path = path.as_ref();
+    let metadata = path.symlink_metadata()?;
+
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+
+    for entry in std::fs::read_dir(path)?.flatten() {
+        size += get_size(entry.path())?;
+    }
+
+    Ok(size)
+}
+
+/// Mark component stored in given folder as used right now
+pub fn mark_used(folder: impl AsRef<Path>) -> anyhow::Result<()> {
+    let folder = folder.as_ref();
+
+    if folder.is_dir() {
+        std::fs::write(folder.join(LAST_USED_FILE), b"")?;
+    }
+
+    Ok(())
+}
+
+/// Get last usage time of the component stored in given folder
+pub fn last_used(folder: impl AsRef<Path>) -> Option<SystemTime> {
+    folder.as_ref()
+        .join(LAST_USED_FILE)
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// List all the downloaded components from the builds folders of given usages
+pub fn list_downloaded(usages: &[ComponentsUsage]) -> anyhow::Result<Vec<DownloadedComponent>> {
+    let mut components = Vec::new();
+
+    for kind in [ComponentKind::Wine, ComponentKind::Dxvk, ComponentKind::Vkd3d] {
+        let mut folders = usages.iter()
+            .map(|usage| usage.builds(kind).to_path_buf())
+            .collect::<Vec<_>>();
+
+        folders.sort();
+        folders.dedup();
+
+        for folder in folders {
+            let Ok(entries) = std::fs::read_dir(&folder) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().to_string();
+
+                if name.starts_with('.') || !path.is_dir() {
+                    continue;
+                }
+
+                let referenced = usages.iter()
+                    .any(|usage| usage.is_referenced(kind, &name));
+
+                let protected = usages.iter()
+                    .any(|usage| {
+                        let selected = kind == ComponentKind::Wine && usage.selected_wine.as_deref() == Some(name.as_str());
+
+                        selected || usage.is_installed(kind, &name)
+                    });
+
+                // Unreadable files shouldn't break the whole listing
+                let size = get_size(&path).unwrap_or_else(|err| {
+                    tracing::warn!("Failed to get size of the component {name}: {err}");
+
+                    0
+                });
+
+                components.push(DownloadedComponent {
+                    kind,
+                    size,
+                    last_used: last_used(&path),
+                    referenced,
+                    protected,
+                    name,
+                    path
+                });
+            }
+        }
+    }
+
+    Ok(components)
+}
+
+/// List downloaded components which are not referenced by any of given usages
+pub fn list_unused(usages: &[ComponentsUsage]) -> anyhow::Result<Vec<DownloadedComponent>> {
+    Ok(list_downloaded(usages)?
+        .into_iter()
+        .filter(|component| !component.referenced && !component.protected)
+        .collect())
+}
+
+/// Remove downloaded component
+///
+/// Protection status is re-checked against given usages
+/// so outdated components listing can't remove used builds
+#[tracing::instrument(level = "debug", ret)]
+pub fn remove(component: &DownloadedComponent, usages: &[ComponentsUsage]) -> anyhow::Result<()> {
+    tracing::debug!("Removing component");
+
+    let protected = usages.iter().any(|usage| {
+        let selected = component.kind == ComponentKind::Wine && usage.selected_wine.as_deref() == Some(component.name.as_str());
+
+        selected || usage.is_installed(component.kind, &component.name)
+    });
+
+    if component.protected || protected {
+        anyhow::bail!("Component {} is currently used and can't be removed", component.name);
+    }
+
+    // Only remove folders which are stored in some builds folder
+    let in_builds = usages.iter()
+        .any(|usage| component.path.parent() == Some(usage.builds(component.kind)));
+
+    if !in_builds {
+        anyhow::bail!("Component {} is not stored in any known builds folder", component.name);
+    }
+
+    // Don't follow symlinks to custom locations
+    if component.path.is_symlink() {
+        std::fs::remove_file(&component.path)?;
+    }
+
+    else if component.path.exists() {
+        std::fs::remove_dir_all(&component.path)?;
+    }
+
+    Ok(())
+}
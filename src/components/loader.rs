@@ -1,13 +1,48 @@
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use cached::Cached;
 
 use crate::anime_game_core::traits::git_sync::RemoteGitSyncExt;
 
 use super::wine;
 use super::dxvk;
+use super::vkd3d;
+
+/// Get revision of the components index category
+///
+/// Returns the latest modification time of the `components.json` file,
+/// all the files within the category folder and the git index, which is
+/// rewritten by every sync. This value is used as a part of the cache key
+/// so the cached values become outdated as soon as the index files are updated
+pub fn index_revision(index: &Path, category: &str) -> Option<SystemTime> {
+    let modified = |path: PathBuf| {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+
+    let mut revision = modified(index.join("components.json"))
+        .max(modified(index.join(".git/index")));
+
+    if let Ok(entries) = std::fs::read_dir(index.join(category)) {
+        for entry in entries.flatten() {
+            if let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) {
+                revision = revision.max(Some(modified));
+            }
+        }
+    }
+
+    revision
+}
 
 /// Try to get wine versions from components index
 #[tracing::instrument(level = "debug")]
-#[cached::proc_macro::cached(key = "PathBuf", convert = r##"{ index.to_path_buf() }"##, result)]
+#[cached::proc_macro::cached(
+    key = "(PathBuf, Option<SystemTime>)",
+    convert = r##"{ (index.to_path_buf(), index_revision(index, "wine")) }"##,
+    result
+)]
 pub fn get_wine_versions(index: &Path) -> anyhow::Result<Vec<wine::Group>> {
     tracing::debug!("Getting wine versions");
 
@@ -78,7 +113,11 @@ pub fn get_wine_versions(index: &Path) -> anyhow::Result<Vec<wine::Group>> {
 
 /// Try to get dxvk versions from components index
 #[tracing::instrument(level = "debug")]
-#[cached::proc_macro::cached(key = "PathBuf", convert = r##"{ index.to_path_buf() }"##, result)]
+#[cached::proc_macro::cached(
+    key = "(PathBuf, Option<SystemTime>)",
+    convert = r##"{ (index.to_path_buf(), index_revision(index, "dxvk")) }"##,
+    result
+)]
 pub fn get_dxvk_versions(index: &Path) -> anyhow::Result<Vec<dxvk::Group>> {
     tracing::debug!("Getting dxvk versions");
 
@@ -147,6 +186,82 @@ pub fn get_dxvk_versions(index: &Path) -> anyhow::Result<Vec<dxvk::Group>> {
     }
 }
 
+/// Try to get vkd3d versions from components index
+#[tracing::instrument(level = "debug")]
+#[cached::proc_macro::cached(
+    key = "(PathBuf, Option<SystemTime>)",
+    convert = r##"{ (index.to_path_buf(), index_revision(index, "vkd3d")) }"##,
+    result
+)]
+pub fn get_vkd3d_versions(index: &Path) -> anyhow::Result<Vec<vkd3d::Group>> {
+    tracing::debug!("Getting vkd3d versions");
+
+    let components = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(index.join("components.json"))?)?;
+
+    match components.get("vkd3d") {
+        Some(vkd3d) => match vkd3d.as_array() {
+            Some(groups) => {
+                let mut vkd3d_groups = Vec::with_capacity(groups.len());
+
+                for group in groups {
+                    let name = match group.get("name") {
+                        Some(name) => match name.as_str() {
+                            Some(name) => name.to_string(),
+                            None => anyhow::bail!("Wrong components index structure: vkd3d group's name entry must be a string")
+                        }
+
+                        None => anyhow::bail!("Wrong components index structure: vkd3d group's name not found")
+                    };
+
+                    let title = match group.get("title") {
+                        Some(title) => match title.as_str() {
+                            Some(title) => title.to_string(),
+                            None => anyhow::bail!("Wrong components index structure: vkd3d group's title entry must be a string")
+                        }
+
+                        None => anyhow::bail!("Wrong components index structure: vkd3d group's title not found")
+                    };
+
+                    let versions = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(index.join("vkd3d").join(format!("{name}.json")))?)?;
+
+                    let mut vkd3d_versions = Vec::new();
+
+                    match versions.as_array() {
+                        Some(versions) => {
+                            for version in versions {
+                                vkd3d_versions.push(vkd3d::Version {
+                                    name: version["name"].as_str().unwrap().to_string(),
+                                    title: version["title"].as_str().unwrap().to_string(),
+                                    version: version["version"].as_str().unwrap().to_string(),
+                                    uri: version["uri"].as_str().unwrap().to_string(),
+                                    format: version["format"].as_str().map(|str| str.to_string()),
+                                    features: version.get("features").map(|v| v.into())
+                                });
+                            }
+                        }
+
+                        None => anyhow::bail!("Wrong components index structure: vkd3d versions must be a list")
+                    }
+
+                    vkd3d_groups.push(vkd3d::Group {
+                        name,
+                        title,
+                        features: group.get("features").map(|v| v.into()),
+                        versions: vkd3d_versions
+                    });
+                }
+
+                Ok(vkd3d_groups)
+            }
+
+            None => anyhow::bail!("Wrong components index structure: vkd3d entry must be a list")
+        }
+
+        // Older components indexes don't have vkd3d builds
+        None => Ok(Vec::new())
+    }
+}
+
 #[derive(Debug)]
 pub struct ComponentsLoader {
     folder: PathBuf
@@ -167,6 +282,52 @@ impl ComponentsLoader {
         }
     }
 
+    /// Remove all the cached components lookups of this index
+    ///
+    /// Cached lookups are keyed on the index revision so they become
+    /// outdated after every sync or files change. This method only frees
+    /// the memory used by them
+    #[tracing::instrument(level = "debug")]
+    pub fn invalidate(&self) {
+        tracing::debug!("Invalidating cached components index");
+
+        if let Ok(mut cache) = GET_WINE_VERSIONS.lock() {
+            let keys = cache.get_store()
+                .keys()
+                .filter(|(path, _)| path == &self.folder)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            for key in keys {
+                cache.cache_remove(&key);
+            }
+        }
+
+        if let Ok(mut cache) = GET_DXVK_VERSIONS.lock() {
+            let keys = cache.get_store()
+                .keys()
+                .filter(|(path, _)| path == &self.folder)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            for key in keys {
+                cache.cache_remove(&key);
+            }
+        }
+
+        if let Ok(mut cache) = GET_VKD3D_VERSIONS.lock() {
+            let keys = cache.get_store()
+                .keys()
+                .filter(|(path, _)| path == &self.folder)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            for key in keys {
+                cache.cache_remove(&key);
+            }
+        }
+    }
+
     #[inline]
     #[tracing::instrument(level = "debug")]
     /// Try to get wine versions from components index
@@ -180,4 +341,11 @@ impl ComponentsLoader {
     pub fn get_dxvk_versions(&self) -> anyhow::Result<Vec<dxvk::Group>> {
         get_dxvk_versions(&self.folder)
     }
+
+    #[inline]
+    #[tracing::instrument(level = "debug")]
+    /// Try to get vkd3d versions from components index
+    pub fn get_vkd3d_versions(&self) -> anyhow::Result<Vec<vkd3d::Group>> {
+        get_vkd3d_versions(&self.folder)
+    }
 }
@@ -1,3 +1,9 @@
 pub mod loader;
 pub mod wine;
 pub mod dxvk;
+pub mod custom;
+pub mod proton;
+pub mod runner;
+pub mod vkd3d;
+pub mod gc;
+pub mod advisor;
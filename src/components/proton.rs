@@ -0,0 +1,177 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use super::wine;
+
+/// Folders inside of the proton build which contain wine files
+const PROTON_LAYOUTS: &[&str] = &[
+    "files",
+    "dist"
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Proton {
+    /// Name of the proton build's folder
+    pub name: String,
+
+    /// Path to the proton build
+    pub path: PathBuf
+}
+
+impl Proton {
+    /// Try to load proton build from given folder
+    ///
+    /// Returns `None` if there's no `proton` script or wine files in it
+    pub fn from_folder(folder: impl Into<PathBuf>) -> Option<Self> {
+        let path: PathBuf = folder.into();
+
+        if !path.join("proton").exists() {
+            return None;
+        }
+
+        if !PROTON_LAYOUTS.iter().any(|layout| path.join(layout).join("bin").exists()) {
+            return None;
+        }
+
+        Some(Self {
+            name: path.file_name()?.to_string_lossy().to_string(),
+            path
+        })
+    }
+
+    /// Get path to the folder with proton's wine build
+    pub fn wine_folder(&self) -> PathBuf {
+        PROTON_LAYOUTS.iter()
+            .map(|layout| self.path.join(layout))
+            .find(|folder| folder.join("bin").exists())
+            .unwrap_or_else(|| self.path.join(PROTON_LAYOUTS[0]))
+    }
+
+    /// Get wine files of the proton build relative to its folder
+    pub fn files(&self) -> wine::Files {
+        let layout = PROTON_LAYOUTS.iter()
+            .find(|layout| self.path.join(layout).join("bin").exists())
+            .unwrap_or(&PROTON_LAYOUTS[0]);
+
+        let wine64 = format!("{layout}/bin/wine64");
+
+        wine::Files {
+            wine: if self.path.join(&wine64).exists() {
+                wine64.clone()
+            } else {
+                format!("{layout}/bin/wine")
+            },
+
+            wine64: if self.path.join(&wine64).exists() {
+                Some(wine64)
+            } else {
+                None
+            },
+
+            wineserver: Some(format!("{layout}/bin/wineserver")),
+            wineboot: Some(format!("{layout}/bin/wineboot"))
+        }
+    }
+}
+
+/// Get list of existing steam installation folders
+pub fn steam_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+
+    if let Ok(home) = std::env::var("HOME") {
+        let home = PathBuf::from(home);
+
+        roots.push(home.join(".steam/root"));
+        roots.push(home.join(".steam/steam"));
+        roots.push(home.join(".local/share/Steam"));
+
+        // Flatpak steam
+        roots.push(home.join(".var/app/com.valvesoftware.Steam/.steam/root"));
+        roots.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+    }
+
+    let mut existing: Vec<PathBuf> = Vec::with_capacity(roots.len());
+
+    // Steam root folders are usually symlinked to each other
+    for root in roots {
+        if let Ok(root) = root.canonicalize() {
+            if !existing.contains(&root) {
+                existing.push(root);
+            }
+        }
+    }
+
+    existing
+}
+
+/// Find all the proton builds installed in the system
+///
+/// Looks into `compatibilitytools.d` and `steamapps/common` folders
+/// of every found steam installation, and into the system-wide
+/// `/usr/share/steam/compatibilitytools.d` folder
+pub fn discover() -> Vec<Proton> {
+    let mut folders = Vec::new();
+
+    for root in steam_roots() {
+        folders.push(root.join("compatibilitytools.d"));
+        folders.push(root.join("steamapps/common"));
+    }
+
+    folders.push(PathBuf::from("/usr/share/steam/compatibilitytools.d"));
+
+    let mut builds = Vec::new();
+
+    for folder in folders {
+        let Ok(entries) = std::fs::read_dir(folder) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            if let Some(proton) = Proton::from_folder(entry.path()) {
+                if !builds.iter().any(|build: &Proton| build.name == proton.name) {
+                    builds.push(proton);
+                }
+            }
+        }
+    }
+
+    builds.sort_by(|a, b| a.name.cmp(&b.name));
+
+    builds
+}
+
+#[inline]
+/// Find installed proton build with given name
+pub fn find(name: impl AsRef<str>) -> Option<Proton> {
+    let name = name.as_ref();
+
+    discover().into_iter().find(|proton| proton.name == name)
+}
+
+#[inline]
+/// Get `STEAM_COMPAT_DATA_PATH` folder for given wine prefix
+///
+/// Proton stores its prefix in the `pfx` folder within this path,
+/// so we keep compat data next to the prefix and symlink `pfx` to it
+pub fn compat_data_path(prefix: impl AsRef<Path>) -> PathBuf {
+    prefix.as_ref().with_extension("compatdata")
+}
+
+/// Prepare `STEAM_COMPAT_DATA_PATH` folder for given wine prefix
+pub fn prepare_compat_data(prefix: impl AsRef<Path>) -> anyhow::Result<PathBuf> {
+    let prefix = prefix.as_ref();
+    let compat_data = compat_data_path(prefix);
+
+    let pfx = compat_data.join("pfx");
+
+    if !compat_data.exists() {
+        std::fs::create_dir_all(&compat_data)?;
+    }
+
+    if !pfx.is_symlink() && !pfx.exists() {
+        std::os::unix::fs::symlink(prefix, &pfx)?;
+    }
+
+    Ok(compat_data)
+}
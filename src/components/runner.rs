@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+
+use wincompatlib::prelude::*;
+
+use super::wine;
+use super::proton::{self, Proton};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Runner {
+    /// Wine build from the components index or a custom runner
+    Wine(wine::Version),
+
+    /// Proton build run through its `proton` script
+    Proton(Proton),
+
+    /// Run the game through `umu-run` with given proton build,
+    /// or let umu select the latest GE-Proton
+    Umu(Option<Proton>)
+}
+
+impl Runner {
+    /// Get runner name
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Wine(wine) => &wine.name,
+            Self::Proton(proton) => &proton.name,
+
+            Self::Umu(Some(proton)) => &proton.name,
+            Self::Umu(None) => "umu"
+        }
+    }
+
+    #[inline]
+    /// Check if the runner's wine files are known
+    ///
+    /// Returns `false` for umu without selected proton build
+    /// because `umu-run` downloads and selects it itself
+    pub fn has_files(&self) -> bool {
+        !matches!(self, Self::Umu(None))
+    }
+
+    /// Get path to the runner's folder
+    ///
+    /// Returns `None` for wine builds because their folder
+    /// depends on the wine builds path from the config
+    pub fn folder(&self) -> Option<PathBuf> {
+        match self {
+            Self::Wine(_) | Self::Umu(None) => None,
+
+            Self::Proton(proton) |
+            Self::Umu(Some(proton)) => Some(proton.path.clone())
+        }
+    }
+
+    /// Convert runner to the wine version
+    ///
+    /// Proton and umu runners get the launch command specified
+    /// in their features so it's used by the `game::run` functions
+    pub fn to_version(&self) -> wine::Version {
+        match self {
+            Self::Wine(wine) => wine.clone(),
+
+            Self::Proton(proton) => wine::Version {
+                name: proton.name.clone(),
+                title: proton.name.clone(),
+                uri: String::new(),
+                format: None,
+                files: proton.files(),
+                features: Some(wine::Features {
+                    arch: Some(WineArch::Win64),
+                    command: Some(String::from("'%build%/proton' waitforexitandrun")),
+                    recommended: false,
+                    ..wine::Features::default()
+                })
+            },
+
+            Self::Umu(proton) => wine::Version {
+                name: self.name().to_string(),
+                title: self.name().to_string(),
+                uri: String::new(),
+                format: None,
+                files: proton.as_ref()
+                    .map(Proton::files)
+                    .unwrap_or_else(|| wine::Files {
+                        wine: String::from("files/bin/wine64"),
+                        wine64: Some(String::from("files/bin/wine64")),
+                        wineserver: Some(String::from("files/bin/wineserver")),
+                        wineboot: Some(String::from("files/bin/wineboot"))
+                    }),
+                features: Some(wine::Features {
+                    arch: Some(WineArch::Win64),
+                    command: Some(String::from("umu-run")),
+                    recommended: false,
+                    ..wine::Features::default()
+                })
+            }
+        }
+    }
+
+    /// Return runner's features
+    pub fn features(&self, components: impl Into<PathBuf>) -> anyhow::Result<Option<wine::Features>> {
+        match self {
+            Self::Wine(wine) => wine.features(components),
+            _ => Ok(self.to_version().features)
+        }
+    }
+
+    /// Prepare wine prefix to be used by the runner
+    pub fn prepare(&self, prefix: impl AsRef<Path>) -> anyhow::Result<()> {
+        if let Self::Proton(_) = self {
+            proton::prepare_compat_data(prefix)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get proton's `STEAM_COMPAT_DATA_PATH` folder for given wine prefix
+    ///
+    /// Returns `None` if the runner doesn't use it
+    pub fn compat_data_path(&self, prefix: impl AsRef<Path>) -> Option<PathBuf> {
+        match self {
+            Self::Proton(_) => Some(proton::compat_data_path(prefix)),
+
+            Self::Wine(_) | Self::Umu(_) => None
+        }
+    }
+
+    /// Get environment variables needed to run the game with this runner
+    ///
+    /// `prefix` is the original wine prefix path, not the sandboxed one, because
+    /// proton's compat data folder links to it. `WINEPREFIX` must be set by the caller.
+    /// `game_id` is the umu database id of the game (`GAMEID` variable)
+    pub fn get_env_vars(&self, prefix: impl AsRef<Path>, game_id: impl ToString) -> HashMap<&str, String> {
+        match self {
+            Self::Wine(_) => HashMap::new(),
+
+            Self::Proton(_) => {
+                let compat_data = proton::compat_data_path(prefix);
+
+                let client = proton::steam_roots()
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| compat_data.clone());
+
+                HashMap::from([
+                    ("STEAM_COMPAT_DATA_PATH", compat_data.to_string_lossy().to_string()),
+                    ("STEAM_COMPAT_CLIENT_INSTALL_PATH", client.to_string_lossy().to_string())
+                ])
+            }
+
+            Self::Umu(proton) => HashMap::from([
+                ("GAMEID", game_id.to_string()),
+                ("STORE", String::from("none")),
+                ("PROTONPATH", proton.as_ref()
+                    .map(|proton| proton.path.to_string_lossy().to_string())
+                    .unwrap_or_else(|| String::from("GE-Proton")))
+            ])
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+use wincompatlib::prelude::*;
+
+use super::loader::ComponentsLoader;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Group {
+    pub name: String,
+    pub title: String,
+    pub features: Option<Features>,
+    pub versions: Vec<Version>
+}
+
+impl Group {
+    /// Find vkd3d group with given name in components index
+    /// 
+    /// This method will also check all version names within this group, so both `vkd3d-proton` and `vkd3d-proton-2.14.1` will work
+    pub fn find_in<T: Into<PathBuf>, F: AsRef<str>>(components: T, name: F) -> anyhow::Result<Option<Self>> {
+        let name = name.as_ref();
+
+        for group in get_groups(components)? {
+            if group.name == name || group.versions.iter().any(move |version| version.name == name) {
+                return Ok(Some(group));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Features {
+    /// Standard environment variables that are applied when you launch the game
+    /// 
+    /// Available keywords:
+    /// - `%build%` - path to wine build
+    /// - `%prefix%` - path to wine prefix
+    /// - `%temp%` - path to temp folder specified in config file
+    /// - `%launcher%` - path to launcher folder
+    /// - `%game%` - path to the game
+    pub env: HashMap<String, String>,
+
+    pub recommended: bool
+}
+
+impl Default for Features {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            env: HashMap::new(),
+            recommended: true
+        }
+    }
+}
+
+impl From<&JsonValue> for Features {
+    fn from(value: &JsonValue) -> Self {
+        let mut default = Self::default();
+
+        Self {
+            env: match value.get("env") {
+                Some(value) => {
+                    if let Some(object) = value.as_object() {
+                        for (key, value) in object {
+                            if let Some(value) = value.as_str() {
+                                default.env.insert(key.to_string(), value.to_string());
+                            } else {
+                                default.env.insert(key.to_string(), value.to_string());
+                            }
+                        }
+                    }
+
+                    default.env
+                },
+                None => default.env
+            },
+
+            recommended: match value.get("recommended") {
+                Some(value) => value.as_bool().unwrap_or(default.recommended),
+                None => default.recommended
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Version {
+    pub name: String,
+    pub title: String,
+    pub version: String,
+    pub uri: String,
+    pub format: Option<String>,
+    pub features: Option<Features>
+}
+
+impl Version {
+    /// Get latest recommended vkd3d version
+    ///
+    /// Returns an error if the components index has no vkd3d builds
+    pub fn latest<T: Into<PathBuf>>(components: T) -> anyhow::Result<Self> {
+        get_groups(components)?
+            .first()
+            .and_then(|group| group.versions.first())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Components index has no vkd3d builds"))
+    }
+
+    /// Find vkd3d version with given name in components index
+    pub fn find_in<T: Into<PathBuf>, F: AsRef<str>>(components: T, name: F) -> anyhow::Result<Option<Self>> {
+        let name = name.as_ref();
+
+        for group in get_groups(components)? {
+            if let Some(version) = group.versions.into_iter().find(move |version| version.name == name || version.version == name) {
+                return Ok(Some(version));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Find vkd3d group current version belongs to
+    pub fn find_group<T: Into<PathBuf>>(&self, components: T) -> anyhow::Result<Option<Group>> {
+        let name = self.name.as_str();
+
+        for group in get_groups(components)? {
+            if group.versions.iter().any(move |version| version.name == name || version.version == name) {
+                return Ok(Some(group));
+            }
+        }
+
+        Ok(None)
+    }
+
+    #[inline]
+    /// Return this version's features
+    pub fn version_features(&self) -> Option<Features> {
+        self.features.clone()
+    }
+
+    /// Return this version's features if they persist, or
+    /// return group's features otherwise
+    pub fn features_in(&self, group: &Group) -> Option<Features> {
+        if self.features.is_some() {
+            self.features.clone()
+        }
+
+        else {
+            group.features.clone()
+        }
+    }
+
+    /// Return this version's features if they persist, or
+    /// try to return group's features otherwise
+    pub fn features<T: Into<PathBuf>>(&self, components: T) -> anyhow::Result<Option<Features>> {
+        if self.features.is_some() {
+            Ok(self.features.clone())
+        }
+
+        else {
+            match self.find_group(components)? {
+                Some(group) => Ok(group.features),
+                None => Ok(None)
+            }
+        }
+    }
+
+    #[inline]
+    /// Check is current vkd3d downloaded in specified folder
+    pub fn is_downloaded_in<T: Into<PathBuf>>(&self, folder: T) -> bool {
+        folder.into().join(&self.name).exists()
+    }
+
+    /// Install current vkd3d
+    ///
+    /// Copies vkd3d-proton libraries to the wine prefix and sets their
+    /// dll overrides to `native`. Installed version's name is stored in
+    /// the prefix so it can be obtained by the `get_version` function
+    #[tracing::instrument(level = "debug", ret)]
+    pub fn install<T: Into<PathBuf> + std::fmt::Debug>(&self, vkd3ds_folder: T, wine: &Wine) -> anyhow::Result<()> {
+        tracing::debug!("Installing VKD3D");
+
+        let vkd3d_folder = vkd3ds_folder.into().join(&self.name);
+        let windows = wine.prefix.join("drive_c/windows");
+
+        for (arch, system) in [("x64", "system32"), ("x86", "syswow64")] {
+            let system = windows.join(system);
+
+            if !vkd3d_folder.join(arch).exists() || !system.exists() {
+                continue;
+            }
+
+            for library in LIBRARIES {
+                let library = format!("{library}.dll");
+                let source = vkd3d_folder.join(arch).join(&library);
+
+                if source.exists() {
+                    std::fs::copy(source, system.join(library))?;
+                }
+            }
+        }
+
+        for library in LIBRARIES {
+            let output = wine.run_args(["reg", "add", DLL_OVERRIDES_KEY, "/v", library, "/d", "native", "/f"])?
+                .wait_with_output()?;
+
+            if !output.status.success() {
+                anyhow::bail!("Failed to set {library} dll override: {}", String::from_utf8_lossy(&output.stderr));
+            }
+        }
+
+        std::fs::write(wine.prefix.join(VERSION_FILE), &self.name)?;
+
+        super::gc::mark_used(vkd3d_folder)
+    }
+
+    /// Uninstall current vkd3d
+    ///
+    /// Removes vkd3d-proton libraries and their dll overrides from the wine prefix
+    #[tracing::instrument(level = "debug", ret)]
+    pub fn uninstall(&self, wine: &Wine) -> anyhow::Result<()> {
+        tracing::debug!("Uninstalling VKD3D");
+
+        uninstall(wine)
+    }
+}
+
+/// Vkd3d-proton libraries
+const LIBRARIES: &[&str] = &["d3d12", "d3d12core"];
+
+const DLL_OVERRIDES_KEY: &str = "HKEY_CURRENT_USER\\Software\\Wine\\DllOverrides";
+
+/// File in the wine prefix which stores installed vkd3d version's name
+const VERSION_FILE: &str = ".vkd3d-proton";
+
+/// Get name of the vkd3d version installed in the wine prefix
+pub fn get_version(prefix: impl AsRef<Path>) -> anyhow::Result<Option<String>> {
+    let path = prefix.as_ref().join(VERSION_FILE);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    Ok(Some(std::fs::read_to_string(path)?.trim().to_string()))
+}
+
+/// Remove vkd3d-proton from the wine prefix
+///
+/// Wine builtin `d3d12` libraries are restored by running `wineboot -u`
+pub fn uninstall(wine: &Wine) -> anyhow::Result<()> {
+    for library in LIBRARIES {
+        // Deletion fails if there's no override set, so we don't check its status
+        wine.run_args(["reg", "delete", DLL_OVERRIDES_KEY, "/v", library, "/f"])?
+            .wait()?;
+    }
+
+    let windows = wine.prefix.join("drive_c/windows");
+
+    for system in ["system32", "syswow64"] {
+        for library in LIBRARIES {
+            let path = windows.join(system).join(format!("{library}.dll"));
+
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+    }
+
+    wine.run_args(["wineboot", "-u"])?.wait()?;
+
+    let version_file = wine.prefix.join(VERSION_FILE);
+
+    if version_file.exists() {
+        std::fs::remove_file(version_file)?;
+    }
+
+    Ok(())
+}
+
+#[inline]
+pub fn get_groups<T: Into<PathBuf>>(components: T) -> anyhow::Result<Vec<Group>> {
+    ComponentsLoader::new(components).get_vkd3d_versions()
+}
+
+/// List downloaded vkd3d versions in some specific folder
+pub fn get_downloaded<T: Into<PathBuf>>(components: T, folder: T) -> anyhow::Result<Vec<Group>> {
+    let mut downloaded = Vec::new();
+
+    let folder: PathBuf = folder.into();
+
+    for mut group in get_groups(components)? {
+        group.versions.retain(|version| folder.join(&version.name).exists());
+
+        if !group.versions.is_empty() {
+            downloaded.push(group);
+        }
+    }
+
+    Ok(downloaded)
+}
@@ -58,6 +58,11 @@ pub struct Features {
     /// - `%game%` - path to the game
     pub env: HashMap<String, String>,
 
+    /// Synchronization primitives supported by the build (`esync`, `fsync`, `ntsync`)
+    ///
+    /// `None` if not specified, then only esync and fsync are considered supported
+    pub sync: Option<Vec<String>>,
+
     pub recommended: bool
 }
 
@@ -68,6 +73,7 @@ impl Default for Features {
             arch: None,
             command: None,
             env: HashMap::new(),
+            sync: None,
             recommended: true
         }
     }
@@ -106,6 +112,19 @@ impl From<&JsonValue> for Features {
                 None => default.env
             },
 
+            sync: match value.get("sync") {
+                Some(value) => match value.as_array() {
+                    Some(values) => Some(values.iter()
+                        .flat_map(JsonValue::as_str)
+                        .map(|value| value.to_ascii_lowercase())
+                        .collect()),
+
+                    None => default.sync
+                },
+
+                None => default.sync
+            },
+
             recommended: match value.get("recommended") {
                 Some(value) => value.as_bool().unwrap_or(default.recommended),
                 None => default.recommended
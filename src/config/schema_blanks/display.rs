@@ -0,0 +1,88 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use super::window_mode::WindowMode;
+use super::resolution::Resolution;
+
+/// Game window settings translated to the Unity launch arguments
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    pub window_mode: WindowMode,
+
+    /// Game resolution. `None` to keep the one chosen in the game settings
+    pub resolution: Option<Resolution>,
+
+    /// Monitor number starting from 1
+    pub monitor: Option<u64>
+}
+
+impl From<&JsonValue> for DisplayConfig {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        Self {
+            window_mode: value.get("window_mode")
+                .map(WindowMode::from)
+                .unwrap_or(default.window_mode),
+
+            resolution: match value.get("resolution") {
+                Some(value) => {
+                    if value.is_null() {
+                        None
+                    } else {
+                        serde_json::from_value(value.clone()).ok().or(default.resolution)
+                    }
+                },
+                None => default.resolution
+            },
+
+            monitor: match value.get("monitor") {
+                Some(value) => {
+                    if value.is_null() {
+                        None
+                    } else {
+                        value.as_u64().filter(|monitor| *monitor > 0).or(default.monitor)
+                    }
+                },
+                None => default.monitor
+            }
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// Get launch arguments understood by the Unity games
+    ///
+    /// - `Popup` - `-screen-fullscreen 0 -popupwindow`
+    /// - `Fullscreen` - `-screen-fullscreen 1`
+    /// - resolution - `-screen-width <width> -screen-height <height>`
+    /// - monitor - `-monitor <number>`
+    pub fn get_launch_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        match self.window_mode {
+            WindowMode::None => (),
+
+            WindowMode::Popup => args.extend([
+                String::from("-screen-fullscreen 0"),
+                String::from("-popupwindow")
+            ]),
+
+            WindowMode::Fullscreen => args.push(String::from("-screen-fullscreen 1"))
+        }
+
+        if let Some(resolution) = self.resolution {
+            let (width, height) = resolution.get_pair();
+
+            if width > 0 && height > 0 {
+                args.push(format!("-screen-width {width} -screen-height {height}"));
+            }
+        }
+
+        if let Some(monitor) = self.monitor {
+            args.push(format!("-monitor {monitor}"));
+        }
+
+        args
+    }
+}
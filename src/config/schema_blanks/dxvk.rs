@@ -3,7 +3,10 @@ macro_rules! config_impl_dxvk_schema {
     ($launcher_dir:expr) => {
         #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
         pub struct Dxvk {
-            pub builds: PathBuf
+            pub builds: PathBuf,
+
+            /// Options written to the generated `dxvk.conf` file
+            pub options: DxvkOptions
         }
 
         impl Default for Dxvk {
@@ -12,7 +15,8 @@ macro_rules! config_impl_dxvk_schema {
                 let launcher_dir = launcher_dir().expect("Failed to get launcher dir");
 
                 Self {
-                    builds: launcher_dir.join("dxvks")
+                    builds: launcher_dir.join("dxvks"),
+                    options: DxvkOptions::default()
                 }
             }
         }
@@ -26,6 +30,10 @@ macro_rules! config_impl_dxvk_schema {
                         .and_then(|value| value.as_str())
                         .map(PathBuf::from)
                         .unwrap_or(default.builds),
+
+                    options: value.get("options")
+                        .map(DxvkOptions::from)
+                        .unwrap_or(default.options)
                 }
             }
         }
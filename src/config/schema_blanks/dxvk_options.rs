@@ -0,0 +1,145 @@
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+/// First line of the generated `dxvk.conf` file
+///
+/// Files without this header are considered user made and are never overwritten
+const GENERATED_HEADER: &str = "# Generated by the launcher. Manual changes will be overwritten";
+
+/// DXVK runtime options written to the `dxvk.conf` file
+///
+/// https://github.com/doitsujin/dxvk/blob/master/dxvk.conf
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DxvkOptions {
+    /// `dxgi.maxFrameRate` and `d3d9.maxFrameRate`
+    pub max_frame_rate: Option<u64>,
+
+    /// `dxgi.maxFrameLatency` and `d3d9.maxFrameLatency`
+    pub max_frame_latency: Option<u64>,
+
+    /// `dxgi.enableHDR`
+    pub hdr: bool,
+
+    /// `d3d11.cachedDynamicResources`, e.g. `a` or `vi`
+    pub cached_dynamic_resources: Option<String>,
+
+    /// `dxgi.customVendorId`, e.g. `10de` for NVIDIA
+    pub custom_vendor_id: Option<String>,
+
+    /// `dxvk.enableAsync`. Supported by the dxvk-async and dxvk-gplasync builds only
+    pub enable_async: bool
+}
+
+impl From<&JsonValue> for DxvkOptions {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        Self {
+            max_frame_rate: match value.get("max_frame_rate") {
+                Some(value) => value.as_u64().or(default.max_frame_rate),
+                None => default.max_frame_rate
+            },
+
+            max_frame_latency: match value.get("max_frame_latency") {
+                Some(value) => value.as_u64().or(default.max_frame_latency),
+                None => default.max_frame_latency
+            },
+
+            hdr: value.get("hdr")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.hdr),
+
+            cached_dynamic_resources: match value.get("cached_dynamic_resources") {
+                Some(value) => value.as_str().map(String::from).or(default.cached_dynamic_resources),
+                None => default.cached_dynamic_resources
+            },
+
+            custom_vendor_id: match value.get("custom_vendor_id") {
+                Some(value) => value.as_str().map(String::from).or(default.custom_vendor_id),
+                None => default.custom_vendor_id
+            },
+
+            enable_async: value.get("enable_async")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.enable_async)
+        }
+    }
+}
+
+impl DxvkOptions {
+    #[inline]
+    /// Check if all the options have default values, so there's no need in `dxvk.conf` file
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Get `dxvk.conf` file content
+    pub fn to_config(&self) -> String {
+        let mut lines = vec![GENERATED_HEADER.to_string()];
+
+        if let Some(fps) = self.max_frame_rate {
+            lines.push(format!("dxgi.maxFrameRate = {fps}"));
+            lines.push(format!("d3d9.maxFrameRate = {fps}"));
+        }
+
+        if let Some(latency) = self.max_frame_latency {
+            lines.push(format!("dxgi.maxFrameLatency = {latency}"));
+            lines.push(format!("d3d9.maxFrameLatency = {latency}"));
+        }
+
+        if self.hdr {
+            lines.push(String::from("dxgi.enableHDR = True"));
+        }
+
+        if let Some(resources) = &self.cached_dynamic_resources {
+            lines.push(format!("d3d11.cachedDynamicResources = \"{resources}\""));
+        }
+
+        if let Some(vendor_id) = &self.custom_vendor_id {
+            lines.push(format!("dxgi.customVendorId = {}", vendor_id.trim_start_matches("0x")));
+        }
+
+        if self.enable_async {
+            lines.push(String::from("dxvk.enableAsync = True"));
+        }
+
+        lines.join("\n") + "\n"
+    }
+
+    /// Check if `dxvk.conf` file can be written to the given path
+    ///
+    /// Returns `false` if there's already a user made file
+    pub fn is_writable(path: impl AsRef<Path>) -> anyhow::Result<bool> {
+        let path = path.as_ref();
+
+        Ok(!path.exists() || std::fs::read(path)?.starts_with(GENERATED_HEADER.as_bytes()))
+    }
+
+    /// Write `dxvk.conf` file
+    ///
+    /// Returns `false` if there's already a user made file which wasn't overwritten
+    pub fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<bool> {
+        let path = path.as_ref();
+
+        if !Self::is_writable(path)? {
+            return Ok(false);
+        }
+
+        std::fs::write(path, self.to_config())?;
+
+        Ok(true)
+    }
+
+    /// Remove generated `dxvk.conf` file if it exists
+    pub fn remove(path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+
+        if path.exists() && std::fs::read(path)?.starts_with(GENERATED_HEADER.as_bytes()) {
+            std::fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
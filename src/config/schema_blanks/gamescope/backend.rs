@@ -0,0 +1,58 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use enum_ordinalize::Ordinalize;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Ordinalize, Serialize, Deserialize)]
+pub enum GamescopeBackend {
+    #[default]
+    /// Let gamescope choose the backend.
+    Auto,
+
+    /// Nested window using SDL.
+    ///
+    /// ```text
+    /// --backend sdl
+    /// ```
+    Sdl,
+
+    /// Nested window in the wayland compositor.
+    ///
+    /// ```text
+    /// --backend wayland
+    /// ```
+    Wayland,
+
+    /// Direct rendering to the display, without any compositor.
+    ///
+    /// ```text
+    /// --backend drm
+    /// ```
+    Drm,
+
+    /// No window and no display output.
+    ///
+    /// ```text
+    /// --backend headless
+    /// ```
+    Headless
+}
+
+impl GamescopeBackend {
+    pub fn get_command(&self) -> String {
+        match self {
+            Self::Auto     => String::new(),
+            Self::Sdl      => String::from("--backend sdl"),
+            Self::Wayland  => String::from("--backend wayland"),
+            Self::Drm      => String::from("--backend drm"),
+            Self::Headless => String::from("--backend headless")
+        }
+    }
+}
+
+impl From<&JsonValue> for GamescopeBackend {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
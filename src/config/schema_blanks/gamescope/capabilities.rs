@@ -0,0 +1,359 @@
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+static CAPABILITIES: OnceLock<Option<GamescopeCapabilities>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GamescopeFlag {
+    /// Flag names, e.g. `-F` and `--filter`
+    pub names: Vec<String>,
+
+    /// Allowed values if they're listed in the flag description,
+    /// e.g. `(linear, nearest, fsr, nis, pixel)`
+    pub choices: Option<Vec<String>>
+}
+
+impl GamescopeFlag {
+    /// Parse flag from the `gamescope --help` output line
+    ///
+    /// ```text
+    ///   -F, --filter                   upscaler filter (linear, nearest, fsr, nis, pixel)
+    /// ```
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+
+        if !line.starts_with('-') {
+            return None;
+        }
+
+        // Flags are separated from the description by at least two spaces
+        let (flags, description) = line.split_once("  ").unwrap_or((line, ""));
+
+        let names = flags.split(',')
+            .filter_map(|name| name.split_whitespace().next())
+            .map(|name| name.split('=').next().unwrap_or(name).to_string())
+            .filter(|name| name.starts_with('-'))
+            .collect::<Vec<_>>();
+
+        if names.is_empty() {
+            return None;
+        }
+
+        let choices = description.rsplit_once('(')
+            .and_then(|(_, choices)| choices.split_once(')'))
+            .map(|(choices, _)| {
+                choices.split(',')
+                    .map(|choice| choice.trim().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|choices| {
+                choices.len() > 1 && choices.iter().all(|choice| {
+                    !choice.is_empty() && choice.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                })
+            });
+
+        Some(Self {
+            names,
+            choices
+        })
+    }
+}
+
+/// Flags supported by the installed gamescope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GamescopeCapabilities {
+    /// `gamescope --version` output
+    pub version: Option<String>,
+
+    pub flags: Vec<GamescopeFlag>
+}
+
+impl GamescopeCapabilities {
+    /// Parse `gamescope --help` output
+    pub fn parse(help: &str, version: Option<String>) -> Self {
+        Self {
+            version,
+            flags: help.lines()
+                .filter_map(GamescopeFlag::parse)
+                .collect()
+        }
+    }
+
+    /// Run `gamescope --help` and `gamescope --version` to get supported flags
+    ///
+    /// Returns `None` if gamescope is not installed
+    pub fn probe() -> Option<Self> {
+        // Gamescope prints help and version to stderr
+        let output = |arg: &str| {
+            Command::new("gamescope")
+                .arg(arg)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+                .map(|output| {
+                    String::from_utf8_lossy(&output.stdout).to_string() +
+                    &String::from_utf8_lossy(&output.stderr)
+                })
+        };
+
+        let help = output("--help").ok()?;
+
+        let version = output("--version").ok()
+            .and_then(|version| version.lines().find(|line| line.contains("gamescope")).map(String::from));
+
+        Some(Self::parse(&help, version))
+    }
+
+    /// Get cached capabilities of the installed gamescope
+    ///
+    /// Gamescope is probed once per launcher run
+    pub fn get() -> Option<&'static Self> {
+        CAPABILITIES.get_or_init(|| {
+            let capabilities = Self::probe();
+
+            tracing::debug!("Gamescope capabilities: {capabilities:?}");
+
+            capabilities
+        }).as_ref()
+    }
+
+    #[inline]
+    /// Check if the installed gamescope supports given flag
+    ///
+    /// Returns `false` if gamescope is not installed
+    pub fn supports(name: &str) -> bool {
+        Self::get()
+            .map(|capabilities| capabilities.find(name).is_some())
+            .unwrap_or(false)
+    }
+
+    #[inline]
+    /// Find flag with given name
+    pub fn find(&self, name: &str) -> Option<&GamescopeFlag> {
+        self.flags.iter().find(|flag| flag.names.iter().any(|flag_name| flag_name == name))
+    }
+
+    /// Check if the flag and its value are supported
+    ///
+    /// Returns a reason why it's not supported otherwise
+    pub fn check(&self, name: &str, value: Option<&str>) -> Result<(), String> {
+        let Some(flag) = self.find(name) else {
+            return Err(format!("{name} flag is not supported by the installed gamescope"));
+        };
+
+        if let (Some(choices), Some(value)) = (&flag.choices, value) {
+            if !choices.iter().any(|choice| choice == value) {
+                return Err(format!("{name} {value} is not supported by the installed gamescope. Available values: {}", choices.join(", ")));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove flags which are not supported by the installed gamescope
+    ///
+    /// Returns filtered arguments and the reasons of removed flags
+    pub fn filter_args(&self, args: &str) -> (String, Vec<String>) {
+        let mut filtered = Vec::new();
+        let mut issues = Vec::new();
+
+        let mut tokens = args.split_whitespace().peekable();
+
+        while let Some(token) = tokens.next() {
+            let is_flag = token.starts_with('-') && token.parse::<f64>().is_err();
+
+            if !is_flag {
+                filtered.push(token);
+
+                continue;
+            }
+
+            // Flag values don't start with a dash
+            let mut values = Vec::new();
+
+            while let Some(value) = tokens.next_if(|value| !value.starts_with('-') || value.parse::<f64>().is_ok()) {
+                values.push(value);
+            }
+
+            let (name, value) = match token.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (token, values.first().copied())
+            };
+
+            match self.check(name, value) {
+                Ok(()) => {
+                    filtered.push(token);
+                    filtered.extend(values);
+                }
+
+                Err(issue) => issues.push(issue)
+            }
+        }
+
+        (filtered.join(" "), issues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed `gamescope --help` output of the 3.11 release
+    const HELP_3_11: &str = "usage: gamescope [options...] -- [command...]
+
+Options:
+  --help                         show help message
+  -W, --output-width             output width
+  -H, --output-height            output height
+  -w, --nested-width             game width
+  -h, --nested-height            game height
+  -r, --nested-refresh           game refresh rate (frames per second)
+  -m, --max-scale                maximum scale factor
+  -n, --nearest-neighbor-filter  use nearest neighbor filtering
+  -U, --fsr-upscaling            use AMD FidelityFX™ Super Resolution 1.0 for upscaling
+  -Y, --nis-upscaling            use NVIDIA Image Scaling v1.0.3 for upscaling
+  --sharpness, --fsr-sharpness   upscaler sharpness from 0 (max) to 20 (min)
+  --expose-wayland               support wayland clients using xdg-shell
+  -s, --mouse-sensitivity        multiply mouse movement by given decimal number
+  --headless                     use headless backend (no window, no DRM output)
+  --cursor                       path to default cursor image
+  -R, --ready-fd                 notify FD when ready
+  --rt                           Use realtime scheduling
+  -T, --stats-path               write statistics to path
+  -C, --hide-cursor-delay        hide cursor image after delay
+  -e, --steam                    enable Steam integration
+  --xwayland-count               create N xwayland servers
+  --prefer-vk-device             prefer Vulkan device for compositing (ex: 1002:7300)
+  --force-orientation            rotate the internal display (left, right, normal, upsidedown)
+  --force-windows-fullscreen     force windows inside of gamescope to be the size of the nested display (fullscreen)
+  --cursor-scale-height          if specified, sets a base output height to linearly scale the cursor against.
+  --framerate-limit              Set a simple framerate limit. Used as a divisor of the refresh rate, rounds down eg 60 / 59 -> 60fps, 60 / 25 -> 30fps. Default: 0, disabled.
+
+Nested mode options:
+  -o, --nested-unfocused-refresh game refresh rate when unfocused
+  -b, --borderless               make the window borderless
+  -f, --fullscreen               make the window fullscreen
+  -g, --grab                     grab the keyboard
+  -i, --integer-scale            force scale factor to integer
+  --force-grab-cursor            always use relative mouse mode instead of flipping dependent on cursor visibility.
+
+Keyboard shortcuts:
+  Super + F                      toggle fullscreen
+  Super + N                      toggle nearest neighbour filtering
+  Super + U                      toggle FSR upscaling
+";
+
+    /// Trimmed `gamescope --help` output of the 3.14 release
+    const HELP_3_14: &str = "usage: gamescope [options...] -- [command...]
+
+Options:
+  --help                         show help message
+  -W, --output-width             output width
+  -H, --output-height            output height
+  -w, --nested-width             game width
+  -h, --nested-height            game height
+  -r, --nested-refresh           game refresh rate (frames per second)
+  -m, --max-scale                maximum scale factor
+  -S, --scaler                   upscaler type (auto, integer, fit, fill, stretch)
+  -F, --filter                   upscaler filter (linear, nearest, fsr, nis, pixel)
+                                     fsr => AMD FidelityFX™ Super Resolution 1.0
+                                     nis => NVIDIA Image Scaling v1.0.3
+  --sharpness, --fsr-sharpness   upscaler sharpness from 0 (max) to 20 (min)
+  --expose-wayland               support wayland clients using xdg-shell
+  --headless                     use headless backend (no window, no DRM output)
+  --cursor                       path to default cursor image
+  --rt                           Use realtime scheduling
+  -e, --steam                    enable Steam integration
+  --prefer-vk-device             prefer Vulkan device for compositing (ex: 1002:7300)
+  --force-orientation            rotate the internal display (left, right, normal, upsidedown)
+  --hdr-enabled                  enable HDR output (needs Gamescope WSI layer enabled for support from clients)
+                                 If this is not set, and there is a HDR client, it will be tonemapped SDR.
+  --sdr-gamut-wideness           Set the 'wideness' of the gamut for SDR comment. 0 - 1.
+  --hdr-sdr-content-nits         set the luminance of SDR content in nits. Default: 400 nits.
+  --hdr-itm-enable               enable SDR->HDR inverse tone mapping. only works for SDR input.
+  --framerate-limit              Set a simple framerate limit. Used as a divisor of the refresh rate, rounds down eg 60 / 59 -> 60fps, 60 / 25 -> 30fps. Default: 0, disabled.
+  --mangoapp                     Launch with the mangoapp (mangohud) performance overlay enabled. You should use this instead of using mangohud on the game or gamescope.
+  --adaptive-sync                Enable adaptive sync if available (variable rate refresh)
+
+Nested mode options:
+  -o, --nested-unfocused-refresh game refresh rate when unfocused
+  -b, --borderless               make the window borderless
+  -f, --fullscreen               make the window fullscreen
+  -g, --grab                     grab the keyboard
+  --force-grab-cursor            always use relative mouse mode instead of flipping dependent on cursor visibility.
+
+Embedded mode options:
+  -O, --prefer-output            list of connectors in order of preference (ex: DP-1,DP-2,DP-3,HDMI-A-1)
+  --generate-drm-mode            DRM mode generation algorithm (cvt, fixed)
+  --immediate-flips              Enable immediate flips, may result in tearing
+
+Keyboard shortcuts:
+  Super + F                      toggle fullscreen
+  Super + N                      toggle nearest neighbour filtering
+  Super + U                      toggle FSR upscaling
+";
+
+    const ARGS: &str = "-w 1920 -h 1080 -F fsr --sdr-gamut-wideness 0.5 --cursor '/home/user/My Cursors/cursor.png' -f";
+
+    #[test]
+    fn parse_flags() {
+        let capabilities = GamescopeCapabilities::parse(HELP_3_14, None);
+
+        let filter = capabilities.find("-F").unwrap();
+
+        assert_eq!(filter.names, ["-F", "--filter"]);
+        assert_eq!(filter.choices.as_deref(), Some(["linear", "nearest", "fsr", "nis", "pixel"].map(String::from).as_slice()));
+
+        assert_eq!(capabilities.find("--filter"), Some(filter));
+        assert_eq!(capabilities.find("--sharpness"), capabilities.find("--fsr-sharpness"));
+
+        // Flag without two spaces before the description
+        assert!(capabilities.find("--nested-unfocused-refresh").is_some());
+
+        // Examples and notes in parentheses are not choices
+        assert_eq!(capabilities.find("--prefer-vk-device").unwrap().choices, None);
+        assert_eq!(capabilities.find("--prefer-output").unwrap().choices, None);
+        assert_eq!(capabilities.find("--hdr-enabled").unwrap().choices, None);
+        assert_eq!(capabilities.find("-r").unwrap().choices, None);
+
+        // Description continuation lines and shortcuts are not flags
+        assert!(capabilities.find("fsr").is_none());
+        assert!(capabilities.find("Super").is_none());
+    }
+
+    #[test]
+    fn check_flags() {
+        let old = GamescopeCapabilities::parse(HELP_3_11, None);
+        let new = GamescopeCapabilities::parse(HELP_3_14, None);
+
+        assert!(old.check("-F", Some("fsr")).is_err());
+        assert!(old.check("-U", None).is_ok());
+        assert!(old.check("--sdr-gamut-wideness", Some("0.5")).is_err());
+
+        assert!(new.check("-F", Some("fsr")).is_ok());
+        assert!(new.check("--filter", Some("nis")).is_ok());
+        assert!(new.check("-F", Some("bicubic")).is_err());
+        assert!(new.check("-U", None).is_err());
+        assert!(new.check("--sdr-gamut-wideness", Some("0.5")).is_ok());
+    }
+
+    #[test]
+    fn filter_args() {
+        let new = GamescopeCapabilities::parse(HELP_3_14, None);
+
+        assert_eq!(new.filter_args(ARGS), (String::from(ARGS), vec![]));
+        assert_eq!(new.filter_args("--filter=fsr -f"), (String::from("--filter=fsr -f"), vec![]));
+
+        let (args, issues) = new.filter_args("-F bicubic -f");
+
+        assert_eq!(args, "-f");
+        assert_eq!(issues.len(), 1);
+
+        let old = GamescopeCapabilities::parse(HELP_3_11, None);
+
+        let (args, issues) = old.filter_args(ARGS);
+
+        assert_eq!(args, "-w 1920 -h 1080 --cursor '/home/user/My Cursors/cursor.png' -f");
+        assert_eq!(issues.len(), 2);
+    }
+}
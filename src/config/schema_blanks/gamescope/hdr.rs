@@ -0,0 +1,98 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+/// HDR tuning options. Used only with the `hdr_support` option enabled.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamescopeHdr {
+    /// Convert SDR image to HDR (inverse tone mapping).
+    ///
+    /// ```text
+    /// --hdr-itm-enable
+    /// ```
+    pub inverse_tone_mapping: bool,
+
+    /// Brightness of the SDR image used as the inverse tone mapping input, in nits.
+    ///
+    /// ```text
+    /// --hdr-itm-sdr-nits
+    /// ```
+    pub itm_sdr_nits: Option<u64>,
+
+    /// Target brightness of the inverse tone mapping, in nits.
+    ///
+    /// ```text
+    /// --hdr-itm-target-nits
+    /// ```
+    pub itm_target_nits: Option<u64>,
+
+    /// Brightness of the SDR content shown on the HDR display, in nits.
+    ///
+    /// ```text
+    /// --hdr-sdr-content-nits
+    /// ```
+    pub sdr_content_nits: Option<u64>,
+
+    /// How much SDR colors are stretched to the HDR display gamut, in percents.
+    ///
+    /// ```text
+    /// --sdr-gamut-wideness
+    /// ```
+    pub sdr_gamut_wideness: Option<u64>
+}
+
+impl GamescopeHdr {
+    pub fn get_command(&self) -> String {
+        let mut flags = Vec::with_capacity(5);
+
+        if self.inverse_tone_mapping {
+            flags.push(String::from("--hdr-itm-enable"));
+        }
+
+        if let Some(nits) = self.itm_sdr_nits {
+            flags.push(format!("--hdr-itm-sdr-nits {nits}"));
+        }
+
+        if let Some(nits) = self.itm_target_nits {
+            flags.push(format!("--hdr-itm-target-nits {nits}"));
+        }
+
+        if let Some(nits) = self.sdr_content_nits {
+            flags.push(format!("--hdr-sdr-content-nits {nits}"));
+        }
+
+        // Gamescope expects a value from 0 to 1
+        if let Some(wideness) = self.sdr_gamut_wideness {
+            flags.push(format!("--sdr-gamut-wideness {}", wideness.min(100) as f64 / 100.0));
+        }
+
+        flags.join(" ")
+    }
+}
+
+impl From<&JsonValue> for GamescopeHdr {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        let get = |name: &str, default: Option<u64>| match value.get(name) {
+            Some(value) => {
+                if value.is_null() {
+                    None
+                } else {
+                    value.as_u64().or(default)
+                }
+            },
+            None => default
+        };
+
+        Self {
+            inverse_tone_mapping: value.get("inverse_tone_mapping")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.inverse_tone_mapping),
+
+            itm_sdr_nits: get("itm_sdr_nits", default.itm_sdr_nits),
+            itm_target_nits: get("itm_target_nits", default.itm_target_nits),
+            sdr_content_nits: get("sdr_content_nits", default.sdr_content_nits),
+            sdr_gamut_wideness: get("sdr_gamut_wideness", default.sdr_gamut_wideness)
+        }
+    }
+}
@@ -6,6 +6,9 @@ pub mod window_size;
 pub mod framerate;
 pub mod upscaling;
 pub mod options;
+pub mod backend;
+pub mod hdr;
+pub mod capabilities;
 
 pub mod prelude {
     pub use super::Gamescope;
@@ -14,6 +17,9 @@ pub mod prelude {
     pub use super::framerate::GamescopeFramerate;
     pub use super::upscaling::*;
     pub use super::options::GamescopeOptions;
+    pub use super::backend::GamescopeBackend;
+    pub use super::hdr::GamescopeHdr;
+    pub use super::capabilities::{GamescopeCapabilities, GamescopeFlag};
 }
 
 use prelude::*;
@@ -52,7 +58,11 @@ pub struct Gamescope {
     pub options: GamescopeOptions,
 
     /// List of extra gamescope arguments.
-    pub extra_args: String
+    pub extra_args: String,
+
+    /// Fail the launch instead of removing flags
+    /// unsupported by the installed gamescope.
+    pub strict: bool
 }
 
 impl Default for Gamescope {
@@ -66,7 +76,8 @@ impl Default for Gamescope {
             framerate: GamescopeFramerate::default(),
             upscaling: GamescopeUpscaling::default(),
             options: GamescopeOptions::default(),
-            extra_args: String::new()
+            extra_args: String::new(),
+            strict: false
         }
     }
 }
@@ -107,7 +118,11 @@ impl From<&JsonValue> for Gamescope {
             extra_args: value.get("extra_args")
                 .and_then(JsonValue::as_str)
                 .map(String::from)
-                .unwrap_or(default.extra_args)
+                .unwrap_or(default.extra_args),
+
+            strict: value.get("strict")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.strict)
         }
     }
 }
@@ -135,4 +150,30 @@ impl Gamescope {
 
         Some(flags.join(" "))
     }
+
+    /// Get gamescope command with flags checked against the installed gamescope
+    ///
+    /// Unsupported flags are removed with warnings, or an error
+    /// is returned if the `strict` option is enabled
+    pub fn get_checked_command(&self) -> anyhow::Result<Option<String>> {
+        let Some(command) = self.get_command() else {
+            return Ok(None);
+        };
+
+        let Some(capabilities) = GamescopeCapabilities::get() else {
+            anyhow::bail!("Gamescope is enabled but not installed");
+        };
+
+        let (command, issues) = capabilities.filter_args(&command);
+
+        if self.strict && !issues.is_empty() {
+            anyhow::bail!("Unsupported gamescope options: {}", issues.join("; "));
+        }
+
+        for issue in issues {
+            tracing::warn!("{issue}. Flag is removed from the gamescope command");
+        }
+
+        Ok(Some(command))
+    }
 }
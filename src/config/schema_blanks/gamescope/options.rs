@@ -1,7 +1,12 @@
+use std::path::PathBuf;
+
 use serde::{Serialize, Deserialize};
 use serde_json::Value as JsonValue;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+use super::backend::GamescopeBackend;
+use super::hdr::GamescopeHdr;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GamescopeOptions {
     /// Enable HDR output (needs Gamescope WSI layer enabled for support from clients).
     ///
@@ -10,6 +15,47 @@ pub struct GamescopeOptions {
     /// ```
     pub hdr_support: bool,
 
+    /// HDR inverse tone mapping and SDR gamut options.
+    pub hdr: GamescopeHdr,
+
+    /// Gamescope backend.
+    pub backend: GamescopeBackend,
+
+    /// Make the gamescope wayland server available to the game.
+    ///
+    /// ```text
+    /// --expose-wayland
+    /// ```
+    pub expose_wayland: bool,
+
+    /// Prefer Vulkan device for compositing, in the `vendor:device` format (e.g. `1002:73bf`).
+    ///
+    /// ```text
+    /// --prefer-vk-device
+    /// ```
+    pub prefer_vk_device: Option<String>,
+
+    /// Allow tearing flips when the game's frame is ready (lower latency).
+    ///
+    /// ```text
+    /// --immediate-flips
+    /// ```
+    pub immediate_flips: bool,
+
+    /// Path to the default cursor image.
+    ///
+    /// ```text
+    /// --cursor
+    /// ```
+    pub cursor: Option<PathBuf>,
+
+    /// Enable Steam integration.
+    ///
+    /// ```text
+    /// --steam
+    /// ```
+    pub steam: bool,
+
     /// Use realtime scheduling.
     ///
     /// ```text
@@ -31,7 +77,9 @@ pub struct GamescopeOptions {
     /// ```
     pub force_grab_cursor: bool,
 
-    /// Enable mangohud support.
+    /// Draw mangohud by gamescope itself.
+    ///
+    /// Replaces the mangohud layer of the `MangoHUD` hud and uses its config.
     ///
     /// ```text
     /// --mangoapp
@@ -44,6 +92,13 @@ impl Default for GamescopeOptions {
     fn default() -> Self {
         Self {
             hdr_support: false,
+            hdr: GamescopeHdr::default(),
+            backend: GamescopeBackend::default(),
+            expose_wayland: false,
+            prefer_vk_device: None,
+            immediate_flips: false,
+            cursor: None,
+            steam: false,
             realtime_scheduler: false,
             adaptive_sync: false,
             force_grab_cursor: false,
@@ -54,28 +109,54 @@ impl Default for GamescopeOptions {
 
 impl GamescopeOptions {
     pub fn get_command(&self) -> String {
-        let mut flags = Vec::with_capacity(5);
+        let mut flags = Vec::with_capacity(12);
 
         if self.hdr_support {
-            flags.push("--hdr-enabled");
+            flags.push(String::from("--hdr-enabled"));
+            flags.push(self.hdr.get_command());
+        }
+
+        flags.push(self.backend.get_command());
+
+        if self.expose_wayland {
+            flags.push(String::from("--expose-wayland"));
+        }
+
+        if let Some(device) = &self.prefer_vk_device {
+            flags.push(format!("--prefer-vk-device {device}"));
+        }
+
+        if self.immediate_flips {
+            flags.push(String::from("--immediate-flips"));
+        }
+
+        if let Some(cursor) = &self.cursor {
+            // Quotes are escaped since the command is run by bash
+            flags.push(format!("--cursor '{}'", cursor.to_string_lossy().replace('\'', "'\\''")));
+        }
+
+        if self.steam {
+            flags.push(String::from("--steam"));
         }
 
         if self.realtime_scheduler {
-            flags.push("--rt");
+            flags.push(String::from("--rt"));
         }
 
         if self.adaptive_sync {
-            flags.push("--adaptive-sync");
+            flags.push(String::from("--adaptive-sync"));
         }
 
         if self.force_grab_cursor {
-            flags.push("--force-grab-cursor");
+            flags.push(String::from("--force-grab-cursor"));
         }
 
         if self.mangohud {
-            flags.push("--mangoapp");
+            flags.push(String::from("--mangoapp"));
         }
 
+        flags.retain(|flag| !flag.is_empty());
+
         flags.join(" ")
     }
 }
@@ -84,11 +165,50 @@ impl From<&JsonValue> for GamescopeOptions {
     fn from(value: &JsonValue) -> Self {
         let default = Self::default();
 
+        let get_string = |name: &str, default: Option<String>| match value.get(name) {
+            Some(value) => {
+                if value.is_null() {
+                    None
+                } else {
+                    match value.as_str() {
+                        Some(value) => Some(value.to_string()),
+                        None => default
+                    }
+                }
+            },
+            None => default
+        };
+
         Self {
             hdr_support: value.get("hdr_support")
                 .and_then(JsonValue::as_bool)
                 .unwrap_or(default.hdr_support),
 
+            hdr: value.get("hdr")
+                .map(GamescopeHdr::from)
+                .unwrap_or(default.hdr),
+
+            backend: value.get("backend")
+                .map(GamescopeBackend::from)
+                .unwrap_or(default.backend),
+
+            expose_wayland: value.get("expose_wayland")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.expose_wayland),
+
+            prefer_vk_device: get_string("prefer_vk_device", default.prefer_vk_device),
+
+            immediate_flips: value.get("immediate_flips")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.immediate_flips),
+
+            cursor: get_string("cursor", default.cursor.map(|cursor| cursor.to_string_lossy().to_string()))
+                .map(PathBuf::from),
+
+            steam: value.get("steam")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.steam),
+
             realtime_scheduler: value.get("realtime_scheduler")
                 .and_then(JsonValue::as_bool)
                 .unwrap_or(default.realtime_scheduler),
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use super::preset::HudPreset;
+
+/// DXVK HUD elements
+///
+/// https://github.com/doitsujin/dxvk#hud
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DxvkHud {
+    pub preset: HudPreset,
+
+    /// Elements used by the `Custom` preset, e.g. `fps`, `memory` or `scale=1.5`
+    pub elements: Vec<String>
+}
+
+impl Default for DxvkHud {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            preset: HudPreset::default(),
+            elements: vec![
+                String::from("fps"),
+                String::from("frametimes"),
+                String::from("version"),
+                String::from("gpuload")
+            ]
+        }
+    }
+}
+
+impl From<&JsonValue> for DxvkHud {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        Self {
+            preset: value.get("preset")
+                .map(HudPreset::from)
+                .unwrap_or(default.preset),
+
+            elements: match value.get("elements") {
+                Some(value) => match value.as_array() {
+                    Some(values) => values.iter()
+                        .flat_map(JsonValue::as_str)
+                        .map(String::from)
+                        .collect(),
+
+                    None => default.elements
+                },
+
+                None => default.elements
+            }
+        }
+    }
+}
+
+impl DxvkHud {
+    /// Get `DXVK_HUD` variable value
+    pub fn get_value(&self) -> String {
+        match self.preset {
+            HudPreset::Minimal   => String::from("fps"),
+            HudPreset::Full      => String::from("full"),
+            HudPreset::Benchmark => String::from("fps,frametimes,gpuload,memory,pipelines,compiler"),
+
+            HudPreset::Custom => self.elements.join(",")
+        }
+    }
+
+    /// Get environment variables corresponding to used dxvk hud elements
+    pub fn get_env_vars(&self) -> HashMap<&str, String> {
+        let value = self.get_value();
+
+        if value.is_empty() {
+            return HashMap::new();
+        }
+
+        HashMap::from([("DXVK_HUD", value)])
+    }
+}
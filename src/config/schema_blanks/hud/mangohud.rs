@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use enum_ordinalize::Ordinalize;
+
+use super::preset::HudPreset;
+
+/// First line of the generated config file. Files without it are not overwritten
+const GENERATED_HEADER: &str = "# Generated by the launcher. Manual changes will be overwritten";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ordinalize, Serialize, Deserialize)]
+pub enum MangoHudPosition {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight
+}
+
+impl Default for MangoHudPosition {
+    #[inline]
+    fn default() -> Self {
+        Self::TopLeft
+    }
+}
+
+impl From<&JsonValue> for MangoHudPosition {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
+
+impl MangoHudPosition {
+    /// Get `position` option value
+    pub fn get_value(&self) -> &'static str {
+        match self {
+            Self::TopLeft      => "top-left",
+            Self::TopCenter    => "top-center",
+            Self::TopRight     => "top-right",
+            Self::BottomLeft   => "bottom-left",
+            Self::BottomCenter => "bottom-center",
+            Self::BottomRight  => "bottom-right"
+        }
+    }
+}
+
+/// MangoHud options
+///
+/// https://github.com/flightlessmango/MangoHud#hud-configuration
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MangoHud {
+    pub preset: HudPreset,
+
+    /// HUD position. `None` to keep the user's MangoHud config value
+    pub position: Option<MangoHudPosition>,
+
+    /// Metrics used by the `Custom` preset, e.g. `fps`, `gpu_temp` or `font_size=24`
+    ///
+    /// They're applied on top of the user's MangoHud config
+    pub metrics: Vec<String>,
+
+    /// Framerate limit
+    pub fps_limit: Option<u64>,
+
+    /// Folder for the logs. Logging is toggled by the `Shift_L+F2` keys
+    pub log_folder: Option<PathBuf>,
+
+    /// Start logging every frame right after the game launch
+    pub autostart_log: bool,
+
+    /// Don't draw the HUD. Useful for logging only
+    pub hidden: bool,
+
+    /// Write options to this file instead of the `MANGOHUD_CONFIG` variable
+    pub config_file: Option<PathBuf>
+}
+
+impl From<&JsonValue> for MangoHud {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        let get_path = |name: &str, default: Option<PathBuf>| match value.get(name) {
+            Some(value) => {
+                if value.is_null() {
+                    None
+                } else {
+                    match value.as_str() {
+                        Some(value) => Some(PathBuf::from(value)),
+                        None => default
+                    }
+                }
+            },
+            None => default
+        };
+
+        Self {
+            preset: value.get("preset")
+                .map(HudPreset::from)
+                .unwrap_or(default.preset),
+
+            position: match value.get("position") {
+                Some(value) => {
+                    if value.is_null() {
+                        None
+                    } else {
+                        serde_json::from_value(value.clone()).ok().or(default.position)
+                    }
+                },
+                None => default.position
+            },
+
+            metrics: match value.get("metrics") {
+                Some(value) => match value.as_array() {
+                    Some(values) => values.iter()
+                        .flat_map(JsonValue::as_str)
+                        .map(String::from)
+                        .collect(),
+
+                    None => default.metrics
+                },
+
+                None => default.metrics
+            },
+
+            fps_limit: match value.get("fps_limit") {
+                Some(value) => {
+                    if value.is_null() {
+                        None
+                    } else {
+                        value.as_u64().or(default.fps_limit)
+                    }
+                },
+                None => default.fps_limit
+            },
+
+            log_folder: get_path("log_folder", default.log_folder),
+
+            autostart_log: value.get("autostart_log")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.autostart_log),
+
+            hidden: value.get("hidden")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.hidden),
+
+            config_file: get_path("config_file", default.config_file)
+        }
+    }
+}
+
+impl MangoHud {
+    /// Get list of MangoHud options
+    pub fn get_options(&self) -> Vec<String> {
+        let metrics: &[&str] = match self.preset {
+            HudPreset::Minimal => &["fps_only"],
+            HudPreset::Full => &["full"],
+
+            HudPreset::Benchmark => &[
+                "fps",
+                "frametime",
+                "frame_timing",
+                "cpu_stats",
+                "cpu_temp",
+                "gpu_stats",
+                "gpu_temp",
+                "ram",
+                "vram",
+                "histogram"
+            ],
+
+            HudPreset::Custom => &[]
+        };
+
+        let mut options = metrics.iter()
+            .map(|metric| metric.to_string())
+            .collect::<Vec<_>>();
+
+        if self.preset == HudPreset::Custom {
+            options.extend(self.metrics.iter().cloned());
+        }
+
+        if let Some(position) = self.position {
+            options.push(format!("position={}", position.get_value()));
+        }
+
+        if let Some(fps_limit) = self.fps_limit {
+            options.push(format!("fps_limit={fps_limit}"));
+        }
+
+        if let Some(folder) = &self.log_folder {
+            options.push(format!("output_folder={}", folder.to_string_lossy()));
+        }
+
+        if self.autostart_log {
+            options.push(String::from("autostart_log=1"));
+            options.push(String::from("log_interval=0"));
+        }
+
+        if self.hidden {
+            options.push(String::from("no_display"));
+        }
+
+        options
+    }
+
+    /// Get MangoHud config file content
+    pub fn to_config(&self) -> String {
+        let mut lines = vec![GENERATED_HEADER.to_string()];
+
+        lines.extend(self.get_options());
+
+        lines.join("\n") + "\n"
+    }
+
+    /// Write config file if it's specified, and create logs folder
+    ///
+    /// Existing config file is overwritten only if it was generated by the launcher
+    pub fn prepare(&self) -> anyhow::Result<()> {
+        if let Some(path) = &self.config_file {
+            if path.exists() && !std::fs::read(path)?.starts_with(GENERATED_HEADER.as_bytes()) {
+                tracing::warn!("MangoHud config file {path:?} was not generated by the launcher and will not be overwritten");
+            }
+
+            else {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+
+                std::fs::write(path, self.to_config())?;
+            }
+        }
+
+        if let Some(folder) = &self.log_folder {
+            std::fs::create_dir_all(folder)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get environment variables corresponding to used MangoHud options
+    ///
+    /// When the HUD is drawn by gamescope's `--mangoapp` which reads
+    /// the same config variables, `MANGOHUD` is not set
+    pub fn get_env_vars(&self, mangoapp: bool) -> HashMap<&str, String> {
+        let mut vars = HashMap::new();
+
+        if !mangoapp {
+            vars.insert("MANGOHUD", String::from("1"));
+        }
+
+        if let Some(path) = &self.config_file {
+            vars.insert("MANGOHUD_CONFIGFILE", path.to_string_lossy().to_string());
+        }
+
+        else {
+            // MangoHud splits the variable by commas without any escaping
+            let mut options = self.get_options()
+                .into_iter()
+                .filter(|option| {
+                    if option.contains(',') {
+                        tracing::warn!("MangoHud option can't contain commas, use config_file instead: {option}");
+
+                        return false;
+                    }
+
+                    true
+                })
+                .collect::<Vec<_>>();
+
+            if !options.is_empty() {
+                // MANGOHUD_CONFIG replaces the user's config unless read_cfg is set
+                if self.preset == HudPreset::Custom {
+                    options.insert(0, String::from("read_cfg"));
+                }
+
+                vars.insert("MANGOHUD_CONFIG", options.join(","));
+            }
+        }
+
+        vars
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use enum_ordinalize::Ordinalize;
+
+pub mod preset;
+pub mod dxvk;
+pub mod mangohud;
+
+pub mod prelude {
+    pub use super::HUD;
+    pub use super::preset::HudPreset;
+    pub use super::dxvk::DxvkHud;
+    pub use super::mangohud::{MangoHud, MangoHudPosition};
+}
+
+use prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ordinalize, Serialize, Deserialize)]
+pub enum HUD {
+    None,
+    DXVK,
+    MangoHUD
+}
+
+impl Default for HUD {
+    #[inline]
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl From<&JsonValue> for HUD {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
+
+impl HUD {
+    /// Get environment variables corresponding to used wine hud
+    ///
+    /// `mangoapp` tells that gamescope draws mangohud itself with
+    /// its `--mangoapp` flag, so the mangohud layer is not enabled
+    pub fn get_env_vars(&self, dxvk: &DxvkHud, mangohud: &MangoHud, mangoapp: bool) -> HashMap<&str, String> {
+        match self {
+            Self::None => HashMap::new(),
+            Self::DXVK => dxvk.get_env_vars(),
+
+            // Otherwise the hud will be doubled
+            Self::MangoHUD => mangohud.get_env_vars(mangoapp)
+        }
+    }
+
+    #[inline]
+    /// Check if gamescope should draw the hud with its `--mangoapp` flag
+    ///
+    /// Installed gamescope may not support it, see `GamescopeCapabilities::supports`
+    pub fn uses_mangoapp(&self, gamescope_enabled: bool) -> bool {
+        *self == Self::MangoHUD && gamescope_enabled
+    }
+}
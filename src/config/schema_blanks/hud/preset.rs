@@ -0,0 +1,33 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use enum_ordinalize::Ordinalize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ordinalize, Serialize, Deserialize)]
+pub enum HudPreset {
+    /// Framerate only
+    Minimal,
+
+    /// All the available metrics
+    Full,
+
+    /// Framerate, frametimes and hardware load
+    Benchmark,
+
+    /// Metrics from the config
+    Custom
+}
+
+impl Default for HudPreset {
+    #[inline]
+    fn default() -> Self {
+        Self::Custom
+    }
+}
+
+impl From<&JsonValue> for HudPreset {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
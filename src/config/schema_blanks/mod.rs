@@ -4,8 +4,12 @@ pub mod fsr;
 pub mod hud;
 pub mod fps;
 pub mod window_mode;
+pub mod display;
 pub mod dxvk;
+pub mod dxvk_options;
+pub mod vkd3d;
 pub mod sophon;
+pub mod shader_cache;
 
 pub mod wine;
 pub mod gamescope;
@@ -17,10 +21,13 @@ pub mod prelude {
     pub use super::resolution::Resolution;
     pub use super::repairer::Repairer;
     pub use super::fsr::*;
-    pub use super::hud::HUD;
+    pub use super::hud::prelude::*;
     pub use super::fps::Fps;
     pub use super::window_mode::WindowMode;
+    pub use super::display::DisplayConfig;
     pub use super::sophon::SophonConfig;
+    pub use super::dxvk_options::DxvkOptions;
+    pub use super::shader_cache::ShaderCacheConfig;
 
     pub use super::wine::prelude::*;
     pub use super::gamescope::prelude::*;
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+/// Name of the file with generated machine id in the identity folder
+const MACHINE_ID_FILE: &str = "machine-id";
+
+/// Name of the file with machine ids of the accounts in the identity folder
+const ACCOUNTS_FILE: &str = "machine-ids.json";
+
+/// Account name used when there's no active session
+const DEFAULT_ACCOUNT: &str = "default";
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MachineIdMode {
+    #[default]
+    /// Keep the system's machine id
+    Host,
+
+    /// Generate new machine id for every launch
+    Random,
+
+    /// Generate machine id once for every account (active session)
+    Account
+}
+
+impl From<&JsonValue> for MachineIdMode {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxIdentity {
+    /// Spoof `/etc/machine-id`. Default is `Host`
+    pub machine_id: MachineIdMode,
+
+    /// Spoof `USER` and `LOGNAME` variables. Default is `None`
+    ///
+    /// Wine uses it as the prefix user folder name, so the game
+    /// won't see files from the original user folder
+    pub username: Option<String>,
+
+    /// List of `/proc` and `/sys` entries hidden from the game,
+    /// e.g. `/proc/cpuinfo` or `/sys/class/net`. Default is empty
+    pub masked: Vec<String>
+}
+
+impl From<&JsonValue> for SandboxIdentity {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        Self {
+            machine_id: value.get("machine_id")
+                .map(MachineIdMode::from)
+                .unwrap_or(default.machine_id),
+
+            username: match value.get("username") {
+                Some(value) => {
+                    if value.is_null() {
+                        None
+                    } else {
+                        match value.as_str() {
+                            Some(value) => Some(value.to_string()),
+                            None => default.username
+                        }
+                    }
+                },
+                None => default.username
+            },
+
+            masked: match value.get("masked") {
+                Some(value) => match value.as_array() {
+                    Some(values) => {
+                        values.iter()
+                            .flat_map(|value| value.as_str())
+                            .map(|value| value.to_string())
+                            .collect()
+                    },
+                    None => default.masked
+                },
+                None => default.masked
+            }
+        }
+    }
+}
+
+/// Generate random machine id (32 lowercase hex digits)
+pub fn generate_machine_id() -> anyhow::Result<String> {
+    let mut bytes = [0; 16];
+
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+impl SandboxIdentity {
+    /// Write spoofed machine id to the given folder
+    ///
+    /// `session` is the name of the active session. Returns path
+    /// to the machine id file, or `None` if it shouldn't be spoofed
+    pub fn prepare(&self, folder: impl AsRef<Path>, session: Option<&str>) -> anyhow::Result<Option<PathBuf>> {
+        let folder = folder.as_ref();
+
+        let machine_id = match self.machine_id {
+            MachineIdMode::Host => return Ok(None),
+            MachineIdMode::Random => generate_machine_id()?,
+
+            MachineIdMode::Account => {
+                let path = folder.join(ACCOUNTS_FILE);
+
+                let mut accounts: HashMap<String, String> = if path.exists() {
+                    serde_json::from_slice(&std::fs::read(&path)?)?
+                } else {
+                    HashMap::new()
+                };
+
+                let account = session.unwrap_or(DEFAULT_ACCOUNT);
+
+                match accounts.get(account) {
+                    Some(machine_id) => machine_id.clone(),
+
+                    None => {
+                        let machine_id = generate_machine_id()?;
+
+                        accounts.insert(account.to_string(), machine_id.clone());
+
+                        std::fs::create_dir_all(folder)?;
+                        std::fs::write(&path, serde_json::to_string_pretty(&accounts)?)?;
+
+                        machine_id
+                    }
+                }
+            }
+        };
+
+        let path = folder.join(MACHINE_ID_FILE);
+
+        std::fs::create_dir_all(folder)?;
+        std::fs::write(&path, format!("{machine_id}\n"))?;
+
+        Ok(Some(path))
+    }
+
+    /// Return bwrap arguments applying the identity
+    ///
+    /// Must be appended after the `Sandbox::get_command` mounts.
+    /// `machine_id` is the path returned by the `prepare` method
+    ///
+    /// ### Mounts:
+    ///
+    /// | Original | Mounted | Type | Optional |
+    /// | :- | :- | :- | :- |
+    /// | `machine_id` | `/etc/machine-id` | read-only bind | true |
+    /// | `machine_id` | `/var/lib/dbus/machine-id` | read-only bind | true |
+    /// | - | <masked folders> | tmpfs | true |
+    /// | `/dev/null` | <masked files> | read-only bind | true |
+    pub fn get_command(&self, machine_id: Option<&Path>) -> String {
+        let mut command = String::new();
+
+        if let Some(machine_id) = machine_id {
+            let machine_id = machine_id.to_string_lossy();
+
+            command += &format!(" --ro-bind '{machine_id}' /etc/machine-id");
+
+            if Path::new("/var/lib/dbus/machine-id").exists() {
+                command += &format!(" --ro-bind '{machine_id}' /var/lib/dbus/machine-id");
+            }
+        }
+
+        if let Some(username) = &self.username {
+            let username = username.trim();
+
+            command += &format!(" --setenv USER '{username}' --setenv LOGNAME '{username}'");
+        }
+
+        for path in &self.masked {
+            let path = Path::new(path.trim());
+
+            if !(path.starts_with("/proc") || path.starts_with("/sys")) || path.components().any(|component| component.as_os_str() == "..") {
+                tracing::warn!("Only /proc and /sys entries can be masked in the sandbox: {path:?}");
+
+                continue;
+            }
+
+            if path.is_dir() {
+                command += &format!(" --tmpfs '{}'", path.to_string_lossy());
+            }
+
+            else if path.exists() {
+                command += &format!(" --ro-bind /dev/null '{}'", path.to_string_lossy());
+            }
+        }
+
+        command
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SandboxLevel {
+    #[default]
+    /// Bind all the devices and the original `/tmp`
+    Relaxed,
+
+    /// Bind only GPU render nodes, input, sound and ntsync devices.
+    /// Use private `/tmp` and `/run/user` with the display,
+    /// sound and session bus sockets.
+    ///
+    /// Gamescope's DRM backend and controllers
+    /// which use raw HID devices will not work
+    Standard,
+
+    /// Same as `Standard`, but without the session bus.
+    ///
+    /// Gamemode and desktop portals will not work
+    Strict
+}
+
+impl From<&JsonValue> for SandboxLevel {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
+
+/// List entries of the folder which names start with given prefix
+fn list_devices(folder: impl AsRef<Path>, prefix: &str) -> Vec<PathBuf> {
+    let Ok(entries) = folder.as_ref().read_dir() else {
+        return Vec::new();
+    };
+
+    let mut devices = entries.flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+
+    devices.sort();
+
+    devices
+}
+
+impl SandboxLevel {
+    #[inline]
+    /// Check if the sandbox should use private `/dev`, `/tmp` and `/run/user`
+    pub fn is_hardened(&self) -> bool {
+        *self != Self::Relaxed
+    }
+
+    /// Get list of devices which should be bound to the private `/dev`
+    ///
+    /// Returns empty list for the `Relaxed` level since it binds the whole `/dev`
+    pub fn get_devices(&self) -> Vec<PathBuf> {
+        let mut devices = Vec::new();
+
+        if *self == Self::Relaxed {
+            return devices;
+        }
+
+        // Render nodes are enough for vulkan
+        devices.extend(list_devices("/dev/dri", "renderD"));
+
+        // Proprietary nvidia driver uses its own devices
+        devices.extend(list_devices("/dev", "nvidia"));
+
+        devices.push(PathBuf::from("/dev/input"));
+        devices.push(PathBuf::from("/dev/snd"));
+
+        // Used by the NTSync wine synchronization
+        devices.push(PathBuf::from("/dev/ntsync"));
+
+        devices.retain(|device| device.exists());
+
+        devices
+    }
+
+    /// Get list of `XDG_RUNTIME_DIR` sockets which should be bound to the private `/run/user`
+    pub fn get_runtime_sockets(&self) -> Vec<String> {
+        let mut sockets = vec![
+            std::env::var("WAYLAND_DISPLAY").unwrap_or_else(|_| String::from("wayland-0")),
+            String::from("pulse/native"),
+            String::from("pipewire-0")
+        ];
+
+        if *self == Self::Standard {
+            sockets.push(String::from("bus"));
+        }
+
+        sockets
+    }
+}
@@ -4,20 +4,63 @@ use serde::{Serialize, Deserialize};
 use serde_json::Value as JsonValue;
 
 mod mounts;
+mod level;
+mod identity;
 
 pub use mounts::Mounts;
+pub use level::SandboxLevel;
+pub use identity::{SandboxIdentity, MachineIdMode};
+
+/// Files and folders bound to the minimal `/etc`
+const MINIMAL_ETC: &[&str] = &[
+    "/etc/passwd",
+    "/etc/group",
+    "/etc/hosts",
+    "/etc/host.conf",
+    "/etc/resolv.conf",
+    "/etc/nsswitch.conf",
+    "/etc/gai.conf",
+    "/etc/localtime",
+    "/etc/ld.so.cache",
+    "/etc/ld.so.conf",
+    "/etc/ld.so.conf.d",
+    "/etc/alternatives",
+    "/etc/ssl",
+    "/etc/pki",
+    "/etc/ca-certificates",
+    "/etc/fonts",
+    "/etc/vulkan",
+    "/etc/glvnd",
+    "/etc/OpenCL",
+    "/etc/drirc",
+    "/etc/asound.conf",
+    "/etc/pulse",
+    "/etc/pipewire",
+    "/etc/machine-id",
+    "/etc/os-release"
+];
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Sandbox {
     /// Use `bwrap` to run the game. Default is `false`
     pub enabled: bool,
 
+    /// Devices and temp files isolation level. Default is `Relaxed`
+    pub level: SandboxLevel,
+
+    /// Replace `/etc` by tmpfs with only the files needed
+    /// for libraries, fonts, network and sound. Default is `false`
+    pub minimal_etc: bool,
+
     /// Mount tmpfs to `/home`, `/var/home/$USER` and `$HOME`. Default is `true`
     pub isolate_home: bool,
 
     /// Spoof original hostname. Default is `None`
     pub hostname: Option<String>,
 
+    /// Spoof machine id, username and hide system information
+    pub identity: SandboxIdentity,
+
     /// Append additional bwrap arguments. Default is `None`
     pub args: Option<String>,
 
@@ -33,8 +76,11 @@ impl Default for Sandbox {
     fn default() -> Self {
         Self {
             enabled: false,
+            level: SandboxLevel::default(),
+            minimal_etc: false,
             isolate_home: true,
             hostname: None,
+            identity: SandboxIdentity::default(),
             args: None,
             private: vec![],
             mounts: Mounts::default()
@@ -51,6 +97,14 @@ impl From<&JsonValue> for Sandbox {
                 .and_then(JsonValue::as_bool)
                 .unwrap_or(default.enabled),
 
+            level: value.get("level")
+                .map(SandboxLevel::from)
+                .unwrap_or(default.level),
+
+            minimal_etc: value.get("minimal_etc")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.minimal_etc),
+
             isolate_home: value.get("isolate_home")
                 .and_then(JsonValue::as_bool)
                 .unwrap_or(default.isolate_home),
@@ -69,6 +123,10 @@ impl From<&JsonValue> for Sandbox {
                 None => default.hostname
             },
 
+            identity: value.get("identity")
+                .map(SandboxIdentity::from)
+                .unwrap_or(default.identity),
+
             args: match value.get("args") {
                 Some(value) => {
                     if value.is_null() {
@@ -111,24 +169,53 @@ impl Sandbox {
     /// | Original | Mounted | Type | Optional |
     /// | :- | :- | :- | :- |
     /// | `/` | `/` | read-only bind | false |
-    /// | `/tmp` | `/tmp` | bind | false |
+    /// | `/tmp` | `/tmp` | bind | `Relaxed` level |
+    /// | - | `/tmp` | tmpfs | `Standard` and `Strict` levels |
+    /// | `/tmp/.X11-unix` | same | bind | `Standard` and `Strict` levels |
     /// | `/proc` | `/proc` | bind | false |
-    /// | `/dev` | `/dev` | dev bind | false |
+    /// | `/dev` | `/dev` | dev bind | `Relaxed` level |
+    /// | - | `/dev` | private devtmpfs | `Standard` and `Strict` levels |
+    /// | GPU, input and sound devices | same | dev bind | `Standard` and `Strict` levels |
+    /// | - | `/run/user` | tmpfs | `Standard` and `Strict` levels |
+    /// | `$XDG_RUNTIME_DIR` sockets | same | bind | `Standard` and `Strict` levels |
+    /// | `$XAUTHORITY` | same | read-only bind | `Standard` and `Strict` levels |
+    /// | - | `/etc` | tmpfs | true |
+    /// | minimal `/etc` files | same | read-only bind | true |
     /// | - | `/home` | tmpfs | true |
     /// | - | `/var/home/$USER` | tmpfs | true |
     /// | - | `$HOME` | tmpfs | true |
-    /// | `wine_dir` | `/tmp/sandbox/wine` | bind | false |
+    /// | `wine_dir` | `/tmp/sandbox/wine` | bind | true |
     /// | `prefix_dir` | `/tmp/sandbox/prefix` | bind | false |
     /// | `game_dir` | `/tmp/sandbox/game` | bind | false |
     /// | <mounts/read_only> | <mounts/read_only> | read-only bind | true |
     /// | <mounts/binds> | <mounts/binds> | bind | true |
     /// | <mounts/symlinks> | <mounts/symlinks> | symlink | true |
-    pub fn get_command(&self, wine_dir: impl AsRef<str>, prefix_dir: impl AsRef<str>, game_dir: impl AsRef<str>) -> String {
+    pub fn get_command(&self, wine_dir: Option<impl AsRef<str>>, prefix_dir: impl AsRef<str>, game_dir: impl AsRef<str>) -> String {
         let mut command = String::from("bwrap --ro-bind / /");
 
-        command.push_str(" --bind /tmp /tmp");
+        if self.minimal_etc {
+            command.push_str(" --tmpfs /etc");
+
+            for path in MINIMAL_ETC {
+                command += &format!(" --ro-bind-try {path} {path}");
+            }
+        }
+
         command.push_str(" --bind /proc /proc");
-        command.push_str(" --dev-bind /dev /dev");
+
+        if self.level.is_hardened() {
+            command.push_str(" --tmpfs /tmp");
+            command.push_str(" --dev /dev");
+
+            for device in self.level.get_devices() {
+                command += &format!(" --dev-bind '{0}' '{0}'", device.to_string_lossy());
+            }
+        }
+
+        else {
+            command.push_str(" --bind /tmp /tmp");
+            command.push_str(" --dev-bind /dev /dev");
+        }
 
         if let Some(hostname) = &self.hostname {
             command += &format!(" --hostname '{hostname}'");
@@ -160,6 +247,35 @@ impl Sandbox {
             }
         }
 
+        // Bind display and sound sockets after the home isolation
+        // since they can be stored there
+        if self.level.is_hardened() {
+            command.push_str(" --bind-try /tmp/.X11-unix /tmp/.X11-unix");
+
+            if Path::new("/run/user").is_dir() {
+                command.push_str(" --tmpfs /run/user");
+            }
+
+            if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+                let runtime_dir = runtime_dir.trim();
+
+                command += &format!(" --dir '{runtime_dir}'");
+
+                for socket in self.level.get_runtime_sockets() {
+                    // WAYLAND_DISPLAY can be an absolute path
+                    let socket = Path::new(runtime_dir).join(socket);
+
+                    command += &format!(" --bind-try '{0}' '{0}'", socket.to_string_lossy());
+                }
+            }
+
+            // Some compositors store the cookie in the XDG_RUNTIME_DIR,
+            // so it must be bound after the /run/user tmpfs
+            if let Ok(xauthority) = std::env::var("XAUTHORITY") {
+                command += &format!(" --ro-bind-try '{0}' '{0}'", xauthority.trim());
+            }
+        }
+
         for path in &self.private {
             command += &format!(" --tmpfs '{}'", path.trim());
         }
@@ -176,7 +292,11 @@ impl Sandbox {
             command += &format!(" --symlink '{}' '{}'", from.trim(), to.trim());
         }
 
-        command += &format!(" --bind '{}' /tmp/sandbox/wine", wine_dir.as_ref());
+        // Umu downloads proton itself so there's no wine folder
+        if let Some(wine_dir) = wine_dir {
+            command += &format!(" --bind '{}' /tmp/sandbox/wine", wine_dir.as_ref());
+        }
+
         command += &format!(" --bind '{}' /tmp/sandbox/prefix", prefix_dir.as_ref());
         command += &format!(" --bind '{}' /tmp/sandbox/game", game_dir.as_ref());
 
@@ -190,9 +310,173 @@ impl Sandbox {
         command.push_str(" --unshare-cgroup");
 
         if let Some(args) = &self.args {
-            command.push_str(args.trim());
+            command += &format!(" {}", args.trim());
         }
 
         command
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get sandbox command with the same environment in all the tests
+    fn get_command(sandbox: &Sandbox, wine_dir: Option<&str>) -> String {
+        std::env::set_var("HOME", env!("CARGO_MANIFEST_DIR"));
+        std::env::set_var("USER", "sandbox-test-user");
+        std::env::set_var("XDG_RUNTIME_DIR", "/run/user/1000");
+        std::env::set_var("WAYLAND_DISPLAY", "wayland-1");
+        std::env::set_var("XAUTHORITY", "/run/user/1000/xauth_test");
+
+        sandbox.get_command(wine_dir, "/prefix", "/game")
+    }
+
+    /// Get position of the argument in the command. Panics if it's not found
+    fn position(command: &str, arg: &str) -> usize {
+        command.find(arg).unwrap_or_else(|| panic!("{arg} not found in {command}"))
+    }
+
+    #[test]
+    fn relaxed() {
+        let sandbox = Sandbox {
+            enabled: true,
+            ..Sandbox::default()
+        };
+
+        let command = get_command(&sandbox, Some("/wine"));
+
+        assert!(command.starts_with("bwrap --ro-bind / / --bind /proc /proc --bind /tmp /tmp --dev-bind /dev /dev"));
+
+        assert!(!command.contains("--tmpfs /etc"));
+        assert!(!command.contains("--tmpfs /tmp"));
+        assert!(!command.contains("/run/user"));
+
+        // Wine, prefix and game folders are bound before the namespaces flags
+        let wine = position(&command, "--bind '/wine' /tmp/sandbox/wine");
+        let prefix = position(&command, "--bind '/prefix' /tmp/sandbox/prefix");
+        let game = position(&command, "--bind '/game' /tmp/sandbox/game");
+        let die = position(&command, "--die-with-parent");
+
+        assert!(wine < prefix && prefix < game && game < die);
+    }
+
+    #[test]
+    fn hardened_order() {
+        for level in [SandboxLevel::Standard, SandboxLevel::Strict] {
+            let sandbox = Sandbox {
+                enabled: true,
+                level,
+                ..Sandbox::default()
+            };
+
+            let command = get_command(&sandbox, None);
+
+            assert!(command.starts_with("bwrap --ro-bind / / --bind /proc /proc --tmpfs /tmp --dev /dev"));
+
+            assert!(!command.contains("--bind /tmp /tmp"));
+            assert!(!command.contains("--dev-bind /dev /dev"));
+            assert!(!command.contains("/tmp/sandbox/wine"));
+
+            // Devices are bound to the private /dev
+            for device in level.get_devices() {
+                let device = format!("--dev-bind '{0}' '{0}'", device.to_string_lossy());
+
+                assert!(position(&command, "--dev /dev") < position(&command, &device));
+            }
+
+            // Sockets are bound after the home isolation since they can be stored there
+            let home = position(&command, &format!("--tmpfs '{}'", env!("CARGO_MANIFEST_DIR")));
+            let x11 = position(&command, "--bind-try /tmp/.X11-unix /tmp/.X11-unix");
+            let runtime_dir = position(&command, "--dir '/run/user/1000'");
+            let wayland = position(&command, "--bind-try '/run/user/1000/wayland-1' '/run/user/1000/wayland-1'");
+            let pulse = position(&command, "--bind-try '/run/user/1000/pulse/native' '/run/user/1000/pulse/native'");
+            let xauthority = position(&command, "--ro-bind-try '/run/user/1000/xauth_test' '/run/user/1000/xauth_test'");
+            let prefix = position(&command, "--bind '/prefix' /tmp/sandbox/prefix");
+
+            assert!(home < x11 && x11 < runtime_dir);
+            assert!(runtime_dir < wayland && runtime_dir < pulse);
+            assert!(wayland < xauthority && pulse < xauthority);
+            assert!(xauthority < prefix);
+
+            if Path::new("/run/user").is_dir() {
+                let run_user = position(&command, "--tmpfs /run/user");
+
+                assert!(home < run_user && run_user < runtime_dir);
+            }
+
+            let bus = command.contains("'/run/user/1000/bus'");
+
+            assert_eq!(bus, level == SandboxLevel::Standard);
+        }
+    }
+
+    #[test]
+    fn hardened_devices() {
+        let standard = SandboxLevel::Standard.get_devices();
+        let strict = SandboxLevel::Strict.get_devices();
+
+        assert_eq!(standard, strict);
+        assert!(SandboxLevel::Relaxed.get_devices().is_empty());
+
+        // Only render nodes are bound
+        for device in standard {
+            let device = device.to_string_lossy();
+
+            assert!(!device.starts_with("/dev/dri/card") && device != "/dev/dri");
+            assert!(!device.starts_with("/dev/hidraw"));
+        }
+    }
+
+    #[test]
+    fn minimal_etc() {
+        let sandbox = Sandbox {
+            enabled: true,
+            minimal_etc: true,
+            ..Sandbox::default()
+        };
+
+        let command = get_command(&sandbox, Some("/wine"));
+
+        assert!(command.starts_with("bwrap --ro-bind / / --tmpfs /etc --ro-bind-try /etc/passwd /etc/passwd"));
+
+        // Files are bound after the /etc tmpfs
+        let etc = position(&command, "--tmpfs /etc");
+
+        for path in MINIMAL_ETC {
+            assert!(etc < position(&command, &format!("--ro-bind-try {path} {path}")));
+        }
+    }
+
+    #[test]
+    fn user_mounts_and_args() {
+        let mut sandbox = Sandbox {
+            enabled: true,
+            isolate_home: false,
+            hostname: Some(String::from("sandbox")),
+            args: Some(String::from(" --unshare-net ")),
+            private: vec![String::from("/private")],
+            ..Sandbox::default()
+        };
+
+        sandbox.mounts.read_only.insert(String::from("/ro"), String::from("/ro-target"));
+        sandbox.mounts.bind.insert(String::from("/rw"), String::from("/rw-target"));
+        sandbox.mounts.symlinks.insert(String::from("/link"), String::from("/link-target"));
+
+        let command = get_command(&sandbox, Some("/wine"));
+
+        assert!(!command.contains(&format!("--tmpfs '{}'", env!("CARGO_MANIFEST_DIR"))));
+
+        let hostname = position(&command, "--hostname 'sandbox'");
+        let private = position(&command, "--tmpfs '/private'");
+        let read_only = position(&command, "--ro-bind '/ro' '/ro-target'");
+        let bind = position(&command, "--bind '/rw' '/rw-target'");
+        let symlink = position(&command, "--symlink '/link' '/link-target'");
+        let wine = position(&command, "--bind '/wine' /tmp/sandbox/wine");
+
+        assert!(hostname < private && private < read_only);
+        assert!(read_only < bind && bind < symlink && symlink < wine);
+
+        assert!(command.ends_with(" --unshare-cgroup --unshare-net"));
+    }
+}
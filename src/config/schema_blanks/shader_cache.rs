@@ -0,0 +1,39 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShaderCacheConfig {
+    /// Store DXVK, VKD3D and Mesa caches in the launcher's cache folder. Default is `false`
+    ///
+    /// Disabled by default so existing caches are not left cold after an update
+    pub enabled: bool,
+
+    /// Clear caches when the game or DXVK version changes. Default is `true`
+    pub auto_clear: bool
+}
+
+impl Default for ShaderCacheConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_clear: true
+        }
+    }
+}
+
+impl From<&JsonValue> for ShaderCacheConfig {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        Self {
+            enabled: value.get("enabled")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.enabled),
+
+            auto_clear: value.get("auto_clear")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.auto_clear)
+        }
+    }
+}
@@ -0,0 +1,33 @@
+#[macro_export]
+macro_rules! config_impl_vkd3d_schema {
+    ($launcher_dir:expr) => {
+        #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+        pub struct Vkd3d {
+            pub builds: PathBuf
+        }
+
+        impl Default for Vkd3d {
+            #[inline]
+            fn default() -> Self {
+                let launcher_dir = launcher_dir().expect("Failed to get launcher dir");
+
+                Self {
+                    builds: launcher_dir.join("vkd3ds")
+                }
+            }
+        }
+
+        impl From<&JsonValue> for Vkd3d {
+            fn from(value: &JsonValue) -> Self {
+                let default = Self::default();
+
+                Self {
+                    builds: value.get("builds")
+                        .and_then(|value| value.as_str())
+                        .map(PathBuf::from)
+                        .unwrap_or(default.builds),
+                }
+            }
+        }
+    }
+}
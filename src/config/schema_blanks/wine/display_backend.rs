@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use enum_ordinalize::Ordinalize;
+
+use crate::registry::{Registry, RegistryValue};
+use crate::config::schema_blanks::fsr::Fsr;
+use crate::config::schema_blanks::gamescope::Gamescope;
+
+use super::virtual_desktop::VirtualDesktop;
+
+const DRIVERS_KEY: &str = "Software\\Wine\\Drivers";
+
+/// File in the wine prefix which stores the graphics driver written by the launcher
+const WRITTEN_DRIVER_FILE: &str = ".display_backend";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ordinalize, Serialize, Deserialize)]
+pub enum DisplayBackend {
+    /// Let wine choose the graphics driver
+    Auto,
+
+    /// `winex11` driver. Runs under XWayland on wayland desktops
+    X11,
+
+    /// Native `winewayland` driver
+    Wayland
+}
+
+impl Default for DisplayBackend {
+    #[inline]
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+impl From<&JsonValue> for DisplayBackend {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
+
+impl DisplayBackend {
+    /// Get value of the `Graphics` registry key. `None` means wine's default
+    pub fn get_driver(&self) -> Option<&'static str> {
+        match self {
+            Self::Auto    => None,
+            Self::X11     => Some("x11"),
+            Self::Wayland => Some("wayland")
+        }
+    }
+
+    /// Get environment variables corresponding to used display backend
+    pub fn get_env_vars(&self) -> HashMap<&str, &str> {
+        match self {
+            // Proton ignores the registry key and enables the wayland driver by this variable
+            Self::Wayland => HashMap::from([("PROTON_ENABLE_WAYLAND", "1")]),
+
+            Self::Auto | Self::X11 => HashMap::new()
+        }
+    }
+
+    /// Get warnings about settings which don't work with this display backend
+    pub fn get_warnings(&self, virtual_desktop: &VirtualDesktop, fsr: &Fsr, gamescope: &Gamescope) -> Vec<&'static str> {
+        let mut warnings = Vec::new();
+
+        if *self != Self::Wayland {
+            return warnings;
+        }
+
+        if virtual_desktop.enabled {
+            warnings.push("Wayland driver doesn't support wine virtual desktop");
+        }
+
+        if fsr.enabled {
+            warnings.push("Wine fullscreen FSR hack works with the X11 driver only");
+        }
+
+        // Wayland driver can connect to the gamescope's wayland server if it's exposed
+        if gamescope.enabled && !gamescope.options.expose_wayland {
+            warnings.push("Gamescope runs the game in its own XWayland session so the Wayland driver will not be used");
+        }
+
+        warnings
+    }
+
+    /// Write graphics driver to the `user.reg` file of given prefix
+    ///
+    /// The driver is remembered only if this function changed it, and `Auto` removes
+    /// only the remembered driver, so values set by the user with `winecfg` are kept.
+    /// Wineserver must not be running for this prefix, otherwise
+    /// it will overwrite the registry file on exit
+    pub fn write_registry(&self, prefix: impl AsRef<Path>) -> anyhow::Result<()> {
+        let prefix = prefix.as_ref();
+
+        let written_path = prefix.join(WRITTEN_DRIVER_FILE);
+        let written = std::fs::read_to_string(&written_path).ok();
+
+        if self.get_driver().is_none() && written.is_none() {
+            return Ok(());
+        }
+
+        let path = prefix.join("user.reg");
+
+        let mut registry = Registry::from_file(&path)?;
+
+        let current = registry.get_value(DRIVERS_KEY, "Graphics")
+            .and_then(RegistryValue::as_str)
+            .map(String::from);
+
+        match self.get_driver() {
+            Some(driver) => {
+                if current.as_deref() != Some(driver) {
+                    registry.set_value(DRIVERS_KEY, "Graphics", RegistryValue::String(driver.to_string()));
+
+                    registry.write(path)?;
+
+                    std::fs::write(written_path, driver)?;
+                }
+
+                // The value was set by the user, so it must be kept when switching to `Auto`
+                else if written.is_some() && written.as_deref().map(str::trim) != Some(driver) {
+                    std::fs::remove_file(written_path)?;
+                }
+            }
+
+            None => {
+                if current.is_some() && current.as_deref() == written.as_deref().map(str::trim) {
+                    registry.remove_value(DRIVERS_KEY, "Graphics");
+
+                    registry.write(path)?;
+                }
+
+                std::fs::remove_file(written_path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::{HashMap, BTreeMap};
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use crate::registry::{Registry, RegistryValue};
+
+const DLL_OVERRIDES_KEY: &str = "Software\\Wine\\DllOverrides";
+
+/// File in the wine prefix which stores the libraries which overrides
+/// were written to the registry with their original values
+const PERSISTED_OVERRIDES_FILE: &str = ".persisted_dll_overrides";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DllOverrideMode {
+    Native,
+    Builtin,
+
+    /// Try native library first, then builtin
+    NativeBuiltin,
+
+    /// Try builtin library first, then native
+    BuiltinNative,
+
+    /// Don't load the library at all
+    Disabled
+}
+
+impl DllOverrideMode {
+    /// Parse mode from the `WINEDLLOVERRIDES` or registry format
+    /// (`n,b`, `native,builtin`), or from the enum variant name
+    pub fn parse(value: impl AsRef<str>) -> Option<Self> {
+        let value = value.as_ref().trim();
+
+        let modes = value.split(',')
+            .map(|mode| mode.trim().to_ascii_lowercase())
+            .filter(|mode| !mode.is_empty())
+            .collect::<Vec<_>>();
+
+        let modes = modes.iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        match modes.as_slice() {
+            [] | ["d"] | ["disabled"] => Some(Self::Disabled),
+
+            ["n"] | ["native"] => Some(Self::Native),
+            ["b"] | ["builtin"] => Some(Self::Builtin),
+
+            ["n", "b"] | ["native", "builtin"] | ["nativebuiltin"] => Some(Self::NativeBuiltin),
+            ["b", "n"] | ["builtin", "native"] | ["builtinnative"] => Some(Self::BuiltinNative),
+
+            _ => None
+        }
+    }
+
+    /// Get mode in the `WINEDLLOVERRIDES` format
+    pub fn to_env(&self) -> &'static str {
+        match self {
+            Self::Native        => "n",
+            Self::Builtin       => "b",
+            Self::NativeBuiltin => "n,b",
+            Self::BuiltinNative => "b,n",
+            Self::Disabled      => ""
+        }
+    }
+
+    /// Get mode in the registry format
+    pub fn to_registry(&self) -> &'static str {
+        match self {
+            Self::Native        => "native",
+            Self::Builtin       => "builtin",
+            Self::NativeBuiltin => "native,builtin",
+            Self::BuiltinNative => "builtin,native",
+            Self::Disabled      => ""
+        }
+    }
+}
+
+impl From<&JsonValue> for DllOverrideMode {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        value.as_str()
+            .and_then(Self::parse)
+            .unwrap_or(Self::NativeBuiltin)
+    }
+}
+
+/// Wine dll overrides
+///
+/// Overrides can come from several sources which are merged
+/// in the following order, so later ones take precedence:
+///
+/// 1. wine, dxvk and vkd3d features (`WINEDLLOVERRIDES` variable in their `env`)
+/// 2. launcher features (e.g. mods loaders)
+/// 3. `game.wine.dll_overrides` config field
+/// 4. `WINEDLLOVERRIDES` variable in the `game.environment` config field
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DllOverrides {
+    /// Write overrides to the prefix registry instead of the `WINEDLLOVERRIDES` variable
+    pub persist: bool,
+
+    /// Libraries names (e.g. `d3d11`) with their override modes
+    pub libraries: BTreeMap<String, DllOverrideMode>
+}
+
+impl From<&JsonValue> for DllOverrides {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        Self {
+            persist: value.get("persist")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(default.persist),
+
+            libraries: match value.get("libraries") {
+                Some(value) => match value.as_object() {
+                    Some(values) => {
+                        values.iter()
+                            .map(|(library, mode)| (library.to_ascii_lowercase(), DllOverrideMode::from(mode)))
+                            .collect()
+                    },
+                    None => default.libraries
+                },
+                None => default.libraries
+            }
+        }
+    }
+}
+
+impl DllOverrides {
+    /// Parse overrides from the `WINEDLLOVERRIDES` variable format
+    ///
+    /// ```
+    /// use anime_launcher_sdk::config::schema_blanks::prelude::*;
+    ///
+    /// let overrides = DllOverrides::from_env("d3d11=n,b;nvapi,nvapi64=b;winemenubuilder.exe=");
+    ///
+    /// assert_eq!(overrides.libraries.get("d3d11"), Some(&DllOverrideMode::NativeBuiltin));
+    /// assert_eq!(overrides.libraries.get("nvapi64"), Some(&DllOverrideMode::Builtin));
+    /// assert_eq!(overrides.libraries.get("winemenubuilder.exe"), Some(&DllOverrideMode::Disabled));
+    /// ```
+    pub fn from_env(value: impl AsRef<str>) -> Self {
+        let mut overrides = Self::default();
+
+        overrides.merge_env(value);
+
+        overrides
+    }
+
+    /// Merge overrides from the `WINEDLLOVERRIDES` variable format
+    pub fn merge_env(&mut self, value: impl AsRef<str>) {
+        for entry in value.as_ref().split(';') {
+            let (libraries, mode) = entry.split_once('=')
+                .unwrap_or((entry, ""));
+
+            let Some(mode) = DllOverrideMode::parse(mode) else {
+                tracing::warn!("Unknown dll override mode: {entry}");
+
+                continue;
+            };
+
+            for library in libraries.split(',') {
+                let library = library.trim();
+
+                if !library.is_empty() {
+                    self.libraries.insert(library.to_ascii_lowercase(), mode);
+                }
+            }
+        }
+    }
+
+    /// Merge libraries overrides from another set. `persist` flag is not changed
+    pub fn merge(&mut self, overrides: &DllOverrides) {
+        for (library, mode) in &overrides.libraries {
+            self.libraries.insert(library.clone(), *mode);
+        }
+    }
+
+    #[inline]
+    /// Set override mode of given library
+    pub fn set(&mut self, library: impl AsRef<str>, mode: DllOverrideMode) {
+        self.libraries.insert(library.as_ref().to_ascii_lowercase(), mode);
+    }
+
+    /// Get overrides in the `WINEDLLOVERRIDES` variable format
+    pub fn to_env(&self) -> String {
+        self.libraries.iter()
+            .map(|(library, mode)| format!("{library}={}", mode.to_env()))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// Get environment variables corresponding to the overrides
+    ///
+    /// Returns empty map if overrides are persisted in the registry
+    pub fn get_env_vars(&self) -> HashMap<&str, String> {
+        if self.persist || self.libraries.is_empty() {
+            return HashMap::new();
+        }
+
+        HashMap::from([
+            ("WINEDLLOVERRIDES", self.to_env())
+        ])
+    }
+
+    /// Get libraries which overrides were written to the registry of given prefix
+    ///
+    /// Values are the original overrides of these libraries,
+    /// `None` if the library had no override before
+    pub fn get_persisted(prefix: impl AsRef<Path>) -> BTreeMap<String, Option<String>> {
+        std::fs::read(prefix.as_ref().join(PERSISTED_OVERRIDES_FILE))
+            .ok()
+            .and_then(|persisted| serde_json::from_slice(&persisted).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write overrides to the `user.reg` file of given prefix
+    ///
+    /// Original values of the overwritten overrides are remembered, and the ones
+    /// written by previous calls which are not in this set anymore are restored.
+    /// Wineserver must not be running for this prefix, otherwise
+    /// it will overwrite the registry file on exit
+    pub fn write_registry(&self, prefix: impl AsRef<Path>) -> anyhow::Result<()> {
+        let prefix = prefix.as_ref();
+        let path = prefix.join("user.reg");
+
+        let mut registry = Registry::from_file(&path)?;
+        let mut persisted = Self::get_persisted(prefix);
+
+        persisted.retain(|library, original| {
+            if self.libraries.contains_key(library) {
+                return true;
+            }
+
+            restore_value(&mut registry, library, original.take());
+
+            false
+        });
+
+        for (library, mode) in &self.libraries {
+            if !persisted.contains_key(library) {
+                let original = registry.get_value(DLL_OVERRIDES_KEY, library.as_str())
+                    .and_then(RegistryValue::as_str)
+                    .map(String::from);
+
+                persisted.insert(library.clone(), original);
+            }
+
+            registry.set_value(DLL_OVERRIDES_KEY, library.as_str(), RegistryValue::String(mode.to_registry().to_string()));
+        }
+
+        registry.write(path)?;
+
+        std::fs::write(prefix.join(PERSISTED_OVERRIDES_FILE), serde_json::to_string_pretty(&persisted)?)?;
+
+        Ok(())
+    }
+
+    /// Restore overrides changed by `write_registry` in the `user.reg` file of given prefix
+    ///
+    /// Wineserver must not be running for this prefix, otherwise
+    /// it will overwrite the registry file on exit
+    pub fn remove_registry(prefix: impl AsRef<Path>) -> anyhow::Result<()> {
+        let prefix = prefix.as_ref();
+
+        let persisted = Self::get_persisted(prefix);
+
+        if persisted.is_empty() {
+            return Ok(());
+        }
+
+        let path = prefix.join("user.reg");
+
+        let mut registry = Registry::from_file(&path)?;
+
+        for (library, original) in persisted {
+            restore_value(&mut registry, &library, original);
+        }
+
+        registry.write(path)?;
+
+        std::fs::remove_file(prefix.join(PERSISTED_OVERRIDES_FILE))?;
+
+        Ok(())
+    }
+}
+
+/// Set override of given library to its original value, or remove it if there was none
+fn restore_value(registry: &mut Registry, library: &str, original: Option<String>) {
+    match original {
+        Some(original) => registry.set_value(DLL_OVERRIDES_KEY, library, RegistryValue::String(original)),
+        None => { registry.remove_value(DLL_OVERRIDES_KEY, library); }
+    }
+}
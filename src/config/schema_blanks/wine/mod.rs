@@ -1,16 +1,26 @@
 pub mod wine_lang;
+pub mod wine_locale;
 pub mod wine_sync;
+pub mod wine_debug;
+pub mod wine_runner;
 pub mod wine_drives;
 pub mod virtual_desktop;
+pub mod display_backend;
 pub mod shared_libraries;
+pub mod dll_overrides;
 
 pub mod prelude {
     pub use super::wine_drives::*;
 
     pub use super::wine_lang::WineLang;
-    pub use super::wine_sync::WineSync;
+    pub use super::wine_locale::WineLocale;
+    pub use super::wine_sync::{WineSync, WineSyncCapabilities};
+    pub use super::wine_debug::{WineDebug, WineDebugPreset};
+    pub use super::wine_runner::WineRunner;
     pub use super::virtual_desktop::VirtualDesktop;
+    pub use super::display_backend::DisplayBackend;
     pub use super::shared_libraries::SharedLibraries;
+    pub use super::dll_overrides::{DllOverrides, DllOverrideMode};
 }
 
 #[macro_export]
@@ -20,13 +30,32 @@ macro_rules! config_impl_wine_schema {
         pub struct Wine {
             pub prefix: PathBuf,
             pub builds: PathBuf,
+
+            /// Path to the custom runners manifest
+            pub custom_runners: PathBuf,
+
+            pub runner: WineRunner,
             pub selected: Option<String>,
+
+            /// Take prefix snapshot before launching the game with another runner
+            pub auto_snapshot: bool,
+
+            /// Path to the prefix tweaks manifest
+            pub tweaks_manifest: PathBuf,
+
+            /// Prefix tweaks which are applied before the game launch
+            pub tweaks: Vec<String>,
+
             pub sync: WineSync,
+            pub debug: WineDebug,
             pub language: WineLang,
+            pub locale: WineLocale,
             pub borderless: bool,
             pub drives: WineDrives,
             pub virtual_desktop: VirtualDesktop,
-            pub shared_libraries: SharedLibraries
+            pub display_backend: DisplayBackend,
+            pub shared_libraries: SharedLibraries,
+            pub dll_overrides: DllOverrides
         }
 
         impl Default for Wine {
@@ -37,13 +66,22 @@ macro_rules! config_impl_wine_schema {
                 Self {
                     prefix: launcher_dir.join("prefix"),
                     builds: launcher_dir.join("runners"),
+                    custom_runners: launcher_dir.join("custom_runners.json"),
+                    runner: WineRunner::default(),
                     selected: None,
+                    auto_snapshot: false,
+                    tweaks_manifest: launcher_dir.join("tweaks.json"),
+                    tweaks: Vec::new(),
                     sync: WineSync::default(),
+                    debug: WineDebug::default(),
                     language: WineLang::default(),
+                    locale: WineLocale::default(),
                     borderless: false,
                     drives: WineDrives::default(),
                     virtual_desktop: VirtualDesktop::default(),
-                    shared_libraries: SharedLibraries::default()
+                    display_backend: DisplayBackend::default(),
+                    shared_libraries: SharedLibraries::default(),
+                    dll_overrides: DllOverrides::default()
                 }
             }
         }
@@ -63,6 +101,15 @@ macro_rules! config_impl_wine_schema {
                         .map(PathBuf::from)
                         .unwrap_or(default.builds),
 
+                    custom_runners: value.get("custom_runners")
+                        .and_then(|value| value.as_str())
+                        .map(PathBuf::from)
+                        .unwrap_or(default.custom_runners),
+
+                    runner: value.get("runner")
+                        .map(WineRunner::from)
+                        .unwrap_or(default.runner),
+
                     selected: match value.get("selected") {
                         Some(value) => {
                             if value.is_null() {
@@ -77,14 +124,44 @@ macro_rules! config_impl_wine_schema {
                         None => default.selected
                     },
 
+                    auto_snapshot: value.get("auto_snapshot")
+                        .and_then(|value| value.as_bool())
+                        .unwrap_or(default.auto_snapshot),
+
+                    tweaks_manifest: value.get("tweaks_manifest")
+                        .and_then(|value| value.as_str())
+                        .map(PathBuf::from)
+                        .unwrap_or(default.tweaks_manifest),
+
+                    tweaks: match value.get("tweaks") {
+                        Some(value) => match value.as_array() {
+                            Some(values) => {
+                                values.iter()
+                                    .flat_map(|value| value.as_str())
+                                    .map(|value| value.to_string())
+                                    .collect()
+                            },
+                            None => default.tweaks
+                        },
+                        None => default.tweaks
+                    },
+
                     sync: value.get("sync")
                         .map(WineSync::from)
                         .unwrap_or(default.sync),
 
+                    debug: value.get("debug")
+                        .map(WineDebug::from)
+                        .unwrap_or(default.debug),
+
                     language: value.get("language")
                         .map(WineLang::from)
                         .unwrap_or(default.language),
 
+                    locale: value.get("locale")
+                        .map(WineLocale::from)
+                        .unwrap_or(default.locale),
+
                     borderless: value.get("borderless")
                         .and_then(|value| value.as_bool())
                         .unwrap_or(default.borderless),
@@ -97,11 +174,56 @@ macro_rules! config_impl_wine_schema {
                         .map(VirtualDesktop::from)
                         .unwrap_or(default.virtual_desktop),
 
+                    display_backend: value.get("display_backend")
+                        .map(DisplayBackend::from)
+                        .unwrap_or(default.display_backend),
+
                     shared_libraries: value.get("shared_libraries")
                         .map(SharedLibraries::from)
                         .unwrap_or(default.shared_libraries),
+
+                    dll_overrides: value.get("dll_overrides")
+                        .map(DllOverrides::from)
+                        .unwrap_or(default.dll_overrides),
                 }
             }
         }
+
+        #[cfg(feature = "components")]
+        impl Wine {
+            /// Get folder of the wine build with given name
+            ///
+            /// Custom runners and proton builds can be stored outside of the wine
+            /// builds folder so this method should be used instead of `builds.join(name)`
+            pub fn build_folder(&self, name: impl AsRef<str>) -> PathBuf {
+                let name = name.as_ref();
+
+                if self.runner != WineRunner::Wine {
+                    if let Some(proton) = $crate::components::proton::find(name) {
+                        return proton.path;
+                    }
+                }
+
+                match $crate::components::custom::get_manifest(&self.custom_runners) {
+                    Ok(runners) => runners.into_iter()
+                        .find(|runner| runner.name == name)
+                        .map(|runner| runner.folder(&self.builds))
+                        .unwrap_or_else(|| self.builds.join(name)),
+
+                    Err(err) => {
+                        tracing::warn!("Failed to read custom runners manifest: {err}");
+
+                        self.builds.join(name)
+                    }
+                }
+            }
+
+            #[inline]
+            /// List custom runners from the manifest and the ones
+            /// automatically detected in the wine builds folder
+            pub fn get_custom_runners(&self, components: impl Into<PathBuf>) -> anyhow::Result<Vec<$crate::components::custom::CustomRunner>> {
+                $crate::components::custom::get_custom(components, &self.custom_runners, &self.builds)
+            }
+        }
     };
 }
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use enum_ordinalize::Ordinalize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ordinalize, Serialize, Deserialize)]
+pub enum WineDebugPreset {
+    /// Disable all the debug output (`-all`)
+    Silent,
+
+    /// Don't change `WINEDEBUG` variable
+    Default,
+
+    /// Exceptions and loaded libraries (`+seh,+loaddll`)
+    Loader,
+
+    /// Channels from the `channels` field
+    Custom
+}
+
+impl Default for WineDebugPreset {
+    #[inline]
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+impl From<&JsonValue> for WineDebugPreset {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WineDebug {
+    pub preset: WineDebugPreset,
+
+    /// Debug channels used by the `Custom` preset, e.g. `+relay,-heap`
+    pub channels: String,
+
+    /// Write wine debug output (game's stderr) to this file instead of the `game.log`
+    pub trace_file: Option<PathBuf>
+}
+
+impl From<&JsonValue> for WineDebug {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        Self {
+            preset: value.get("preset")
+                .map(WineDebugPreset::from)
+                .unwrap_or(default.preset),
+
+            channels: value.get("channels")
+                .and_then(|value| value.as_str())
+                .map(String::from)
+                .unwrap_or(default.channels),
+
+            trace_file: match value.get("trace_file") {
+                Some(value) => {
+                    if value.is_null() {
+                        None
+                    } else {
+                        match value.as_str() {
+                            Some(value) => Some(PathBuf::from(value)),
+                            None => default.trace_file
+                        }
+                    }
+                },
+                None => default.trace_file
+            }
+        }
+    }
+}
+
+impl WineDebug {
+    #[inline]
+    /// Trace given debug channels to the file without changing other settings
+    pub fn trace(channels: impl ToString, trace_file: impl Into<PathBuf>) -> Self {
+        Self {
+            preset: WineDebugPreset::Custom,
+            channels: channels.to_string(),
+            trace_file: Some(trace_file.into())
+        }
+    }
+
+    /// Get `WINEDEBUG` value. `None` if the variable shouldn't be changed
+    pub fn channels(&self) -> Option<&str> {
+        match self.preset {
+            WineDebugPreset::Silent  => Some("-all"),
+            WineDebugPreset::Default => None,
+            WineDebugPreset::Loader  => Some("+seh,+loaddll"),
+
+            WineDebugPreset::Custom if self.channels.trim().is_empty() => None,
+            WineDebugPreset::Custom => Some(self.channels.trim())
+        }
+    }
+
+    /// Get environment variables corresponding to used debug preset
+    pub fn get_env_vars(&self) -> HashMap<&str, &str> {
+        match self.channels() {
+            Some(channels) => HashMap::from([("WINEDEBUG", channels)]),
+            None => HashMap::new()
+        }
+    }
+}
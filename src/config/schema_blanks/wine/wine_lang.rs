@@ -37,22 +37,29 @@ impl From<&JsonValue> for WineLang {
 }
 
 impl WineLang {
+    /// Get locale corresponding to used wine language
+    pub fn get_locale(&self) -> Option<&'static str> {
+        match self {
+            Self::System => None,
+
+            Self::English    => Some("en_US.UTF-8"),
+            Self::Russian    => Some("ru_RU.UTF-8"),
+            Self::German     => Some("de_DE.UTF-8"),
+            Self::Portuguese => Some("pt_PT.UTF-8"),
+            Self::Polish     => Some("pl_PL.UTF-8"),
+            Self::French     => Some("fr_FR.UTF-8"),
+            Self::Spanish    => Some("es_ES.UTF-8"),
+            Self::Chinese    => Some("zh_CN.UTF-8"),
+            Self::Japanese   => Some("ja_JP.UTF-8"),
+            Self::Korean     => Some("ko_KR.UTF-8"),
+            Self::Indonesian => Some("id_ID.UTF-8")
+        }
+    }
+
     /// Get environment variables corresponding to used wine language
     pub fn get_env_vars(&self) -> HashMap<&str, &str> {
-        let lang = match self {
-            Self::System => return HashMap::new(),
-
-            Self::English    => "en_US.UTF-8",
-            Self::Russian    => "ru_RU.UTF-8",
-            Self::German     => "de_DE.UTF-8",
-            Self::Portuguese => "pt_PT.UTF-8",
-            Self::Polish     => "pl_PL.UTF-8",
-            Self::French     => "fr_FR.UTF-8",
-            Self::Spanish    => "es_ES.UTF-8",
-            Self::Chinese    => "zh_CN.UTF-8",
-            Self::Japanese   => "ja_JP.UTF-8",
-            Self::Korean     => "ko_KR.UTF-8",
-            Self::Indonesian => "id_ID.UTF-8"
+        let Some(lang) = self.get_locale() else {
+            return HashMap::new();
         };
 
         HashMap::from([
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use crate::registry::{Registry, RegistryValue};
+
+use super::wine_lang::WineLang;
+
+const CODEPAGE_KEY: &str = "System\\CurrentControlSet\\Control\\Nls\\CodePage";
+
+/// File in the wine prefix which stores its codepages before they were changed by the custom locale
+const ORIGINAL_CODEPAGES_FILE: &str = ".original_codepages";
+
+/// ANSI and OEM codepages of western locales
+const WESTERN_CODEPAGES: (&str, &str) = ("1252", "437");
+
+/// Folder with timezones database
+const ZONEINFO_FOLDER: &str = "/usr/share/zoneinfo";
+
+/// Extended locale and timezone emulation
+///
+/// Locales are in the `language_TERRITORY.CODESET` format (e.g. `uk_UA.UTF-8`)
+/// and must be generated in the system to work
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WineLocale {
+    /// Main locale. Takes precedence over the `language` field of the wine config
+    pub locale: Option<String>,
+
+    /// Dates and times formatting locale (`LC_TIME`)
+    pub time: Option<String>,
+
+    /// Numbers formatting locale (`LC_NUMERIC`)
+    pub numeric: Option<String>,
+
+    /// Timezone (`TZ`), e.g. `Asia/Shanghai`
+    pub timezone: Option<String>
+}
+
+impl From<&JsonValue> for WineLocale {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        let get = |name: &str, default: Option<String>| match value.get(name) {
+            Some(value) => {
+                if value.is_null() {
+                    None
+                } else {
+                    match value.as_str() {
+                        Some(value) => Some(value.to_string()),
+                        None => default
+                    }
+                }
+            },
+            None => default
+        };
+
+        Self {
+            locale: get("locale", default.locale),
+            time: get("time", default.time),
+            numeric: get("numeric", default.numeric),
+            timezone: get("timezone", default.timezone)
+        }
+    }
+}
+
+/// Normalize locale name to the `locale -a` output format (`en_US.UTF-8` -> `en_us.utf8`)
+fn normalize(locale: &str) -> String {
+    match locale.split_once('.') {
+        Some((name, codeset)) => format!("{}.{}", name, codeset.replace('-', "")).to_ascii_lowercase(),
+        None => locale.to_ascii_lowercase()
+    }
+}
+
+/// List locales generated in the system (`locale -a`)
+pub fn get_available() -> anyhow::Result<Vec<String>> {
+    let output = Command::new("locale")
+        .arg("-a")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+/// Get ANSI and OEM codepages used by windows for given locale
+///
+/// `None` if the locale uses western codepages
+pub fn get_codepages(locale: impl AsRef<str>) -> Option<(u16, u16)> {
+    let locale = locale.as_ref();
+
+    let name = locale.split(['.', '@']).next().unwrap_or(locale);
+    let language = name.split('_').next().unwrap_or(name);
+
+    match (language, name) {
+        (_, "zh_TW" | "zh_HK" | "zh_MO") => Some((950, 950)),
+
+        ("zh", _) => Some((936, 936)),
+        ("ja", _) => Some((932, 932)),
+        ("ko", _) => Some((949, 949)),
+        ("th", _) => Some((874, 874)),
+        ("vi", _) => Some((1258, 1258)),
+
+        ("ru" | "uk" | "be" | "bg" | "sr" | "mk", _) => Some((1251, 866)),
+        ("pl" | "cs" | "sk" | "hu" | "hr" | "sl" | "ro", _) => Some((1250, 852)),
+        ("el", _) => Some((1253, 737)),
+        ("tr", _) => Some((1254, 857)),
+        ("he", _) => Some((1255, 862)),
+        ("ar" | "fa", _) => Some((1256, 720)),
+        ("lt" | "lv" | "et", _) => Some((1257, 775)),
+
+        _ => None
+    }
+}
+
+impl WineLocale {
+    /// Get main locale. Falls back to the given wine language
+    pub fn get_locale(&self, language: WineLang) -> Option<String> {
+        self.locale.clone().or_else(|| language.get_locale().map(String::from))
+    }
+
+    /// Get list of issues with the configured locales and timezone
+    pub fn validate(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+
+        let locales = [&self.locale, &self.time, &self.numeric]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        if !locales.is_empty() {
+            match get_available() {
+                Ok(available) => {
+                    let available = available.iter()
+                        .map(|locale| normalize(locale))
+                        .collect::<Vec<_>>();
+
+                    for locale in locales {
+                        if !available.contains(&normalize(locale)) {
+                            issues.push(format!("Locale {locale} is not available in the system"));
+                        }
+                    }
+                }
+
+                Err(err) => issues.push(format!("Failed to list system locales: {err}"))
+            }
+        }
+
+        if let Some(timezone) = &self.timezone {
+            let timezone = timezone.trim_start_matches(':');
+
+            if timezone.contains("..") || !Path::new(ZONEINFO_FOLDER).join(timezone).is_file() {
+                issues.push(format!("Timezone {timezone} is not available in the system"));
+            }
+        }
+
+        issues
+    }
+
+    /// Get environment variables corresponding to used locales and timezone
+    pub fn get_env_vars(&self, language: WineLang) -> HashMap<&str, String> {
+        let mut vars = HashMap::new();
+
+        if let Some(locale) = self.get_locale(language) {
+            vars.insert("LANG", locale.clone());
+            vars.insert("HOST_LANG", locale.clone());
+
+            // LC_ALL takes precedence over other LC_* variables,
+            // so it can't be used with the formatting locales
+            if self.time.is_none() && self.numeric.is_none() {
+                vars.insert("LC_ALL", locale.clone());
+                vars.insert("HOST_LC_ALL", locale);
+            }
+
+            else {
+                // Empty value is the same as unset one
+                vars.insert("LC_ALL", String::new());
+                vars.insert("HOST_LC_ALL", String::new());
+
+                vars.insert("LC_CTYPE", locale.clone());
+                vars.insert("LC_MESSAGES", locale);
+            }
+        }
+
+        else if self.time.is_some() || self.numeric.is_some() {
+            vars.insert("LC_ALL", String::new());
+            vars.insert("HOST_LC_ALL", String::new());
+        }
+
+        if let Some(time) = &self.time {
+            vars.insert("LC_TIME", time.clone());
+        }
+
+        if let Some(numeric) = &self.numeric {
+            vars.insert("LC_NUMERIC", numeric.clone());
+        }
+
+        if let Some(timezone) = &self.timezone {
+            vars.insert("TZ", timezone.clone());
+        }
+
+        vars
+    }
+
+    /// Write codepages of the custom locale to the `system.reg` file of given prefix
+    ///
+    /// Original codepages of the prefix are remembered and restored
+    /// when the locale is unset or uses western codepages.
+    /// Wineserver must not be running for this prefix, otherwise
+    /// it will overwrite the registry file on exit
+    pub fn write_registry(&self, prefix: impl AsRef<Path>) -> anyhow::Result<()> {
+        let prefix = prefix.as_ref();
+
+        let original_path = prefix.join(ORIGINAL_CODEPAGES_FILE);
+        let original = std::fs::read_to_string(&original_path).ok();
+
+        let codepages = self.locale.as_ref().and_then(get_codepages);
+
+        // Nothing was changed by the launcher and nothing should be
+        if codepages.is_none() && original.is_none() {
+            return Ok(());
+        }
+
+        let path = prefix.join("system.reg");
+
+        let mut registry = Registry::from_file(&path)?;
+
+        let current = (
+            registry.get_value(CODEPAGE_KEY, "ACP").and_then(RegistryValue::as_str).map(String::from),
+            registry.get_value(CODEPAGE_KEY, "OEMCP").and_then(RegistryValue::as_str).map(String::from)
+        );
+
+        let (ansi, oem) = match codepages {
+            Some((ansi, oem)) => {
+                if original.is_none() {
+                    let (original_ansi, original_oem) = match &current {
+                        (Some(ansi), Some(oem)) => (ansi.as_str(), oem.as_str()),
+                        _ => WESTERN_CODEPAGES
+                    };
+
+                    std::fs::write(&original_path, format!("{original_ansi} {original_oem}"))?;
+                }
+
+                (ansi.to_string(), oem.to_string())
+            }
+
+            None => {
+                let original = original.unwrap_or_default();
+
+                let (ansi, oem) = original.trim()
+                    .split_once(' ')
+                    .unwrap_or(WESTERN_CODEPAGES);
+
+                (ansi.to_string(), oem.to_string())
+            }
+        };
+
+        if current != (Some(ansi.clone()), Some(oem.clone())) {
+            registry.set_value(CODEPAGE_KEY, "ACP", RegistryValue::String(ansi));
+            registry.set_value(CODEPAGE_KEY, "OEMCP", RegistryValue::String(oem));
+
+            registry.write(path)?;
+        }
+
+        if codepages.is_none() {
+            std::fs::remove_file(original_path)?;
+        }
+
+        Ok(())
+    }
+}
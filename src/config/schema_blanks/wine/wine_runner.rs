@@ -0,0 +1,35 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use enum_ordinalize::Ordinalize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ordinalize, Serialize, Deserialize)]
+pub enum WineRunner {
+    /// Run the game with wine build selected from the components index
+    Wine,
+
+    /// Run the game with installed proton build
+    ///
+    /// `selected` field should contain the proton build's folder name
+    Proton,
+
+    /// Run the game through `umu-run`
+    ///
+    /// `selected` field can contain the proton build's folder name.
+    /// Otherwise umu will use the latest GE-Proton
+    Umu
+}
+
+impl Default for WineRunner {
+    #[inline]
+    fn default() -> Self {
+        Self::Wine
+    }
+}
+
+impl From<&JsonValue> for WineRunner {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
@@ -1,15 +1,26 @@
 use std::collections::HashMap;
+use std::path::Path;
 
 use serde::{Serialize, Deserialize};
 use serde_json::Value as JsonValue;
 
 use enum_ordinalize::Ordinalize;
 
+/// Minimal hard limit of opened file descriptors recommended for esync
+pub const ESYNC_FD_LIMIT: u64 = 524288;
+
+/// First kernel version with the `futex_waitv` syscall used by fsync
+pub const FSYNC_KERNEL_VERSION: (u64, u64) = (5, 16);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Ordinalize, Serialize, Deserialize)]
 pub enum WineSync {
     None,
     ESync,
-    FSync
+    FSync,
+    NTSync,
+
+    /// Use the best primitive supported by the system and runner
+    Auto
 }
 
 impl Default for WineSync {
@@ -26,16 +37,137 @@ impl From<&JsonValue> for WineSync {
     }
 }
 
+/// Synchronization primitives supported by the system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WineSyncCapabilities {
+    /// `/dev/ntsync` device is available
+    pub ntsync: bool,
+
+    /// Kernel supports the `futex_waitv` syscall
+    pub fsync: bool,
+
+    /// Hard limit of opened file descriptors is high enough
+    pub esync: bool,
+
+    /// Kernel version (major, minor)
+    pub kernel: Option<(u64, u64)>,
+
+    /// Hard limit of opened file descriptors. `None` if unlimited
+    pub fd_limit: Option<u64>
+}
+
+impl WineSyncCapabilities {
+    /// Inspect the kernel and opened file descriptors limit
+    pub fn probe() -> Self {
+        let kernel = kernel_version();
+        let fd_limit = fd_limit();
+
+        Self {
+            ntsync: Path::new("/dev/ntsync").exists(),
+            fsync: kernel.map(|kernel| kernel >= FSYNC_KERNEL_VERSION).unwrap_or(false),
+            esync: fd_limit.map(|limit| limit >= ESYNC_FD_LIMIT).unwrap_or(true),
+            kernel,
+            fd_limit
+        }
+    }
+}
+
+/// Get kernel version from the `/proc/sys/kernel/osrelease` file
+fn kernel_version() -> Option<(u64, u64)> {
+    let release = std::fs::read_to_string("/proc/sys/kernel/osrelease").ok()?;
+
+    let mut version = release.trim()
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|number| number.parse::<u64>().ok());
+
+    Some((version.next()??, version.next()??))
+}
+
+/// Get hard limit of opened file descriptors (`ulimit -Hn`)
+///
+/// Wine raises the soft limit to the hard one itself
+fn fd_limit() -> Option<u64> {
+    let limits = std::fs::read_to_string("/proc/self/limits").ok()?;
+
+    let line = limits.lines().find(|line| line.starts_with("Max open files"))?;
+
+    // Max open files            1024                 524288               files
+    match line.split_whitespace().nth(4)? {
+        "unlimited" => None,
+        limit => limit.parse().ok()
+    }
+}
+
 impl WineSync {
+    #[inline]
+    /// Get primitive name used in the runners features
+    pub fn feature_name(&self) -> Option<&'static str> {
+        match self {
+            Self::ESync  => Some("esync"),
+            Self::FSync  => Some("fsync"),
+            Self::NTSync => Some("ntsync"),
+
+            Self::None | Self::Auto => None
+        }
+    }
+
+    /// Check if the primitive is supported by the system and runner
+    ///
+    /// `runner` is the list of primitives from the runner's features, if specified
+    pub fn is_supported(&self, capabilities: &WineSyncCapabilities, runner: Option<&[String]>) -> bool {
+        let system = match self {
+            Self::None | Self::Auto => return true,
+
+            Self::ESync  => capabilities.esync,
+            Self::FSync  => capabilities.fsync,
+            Self::NTSync => capabilities.ntsync
+        };
+
+        let runner = match (runner, self.feature_name()) {
+            (Some(features), Some(name)) => features.iter().any(|feature| feature == name),
+
+            // Runners usually don't specify primitives, and while
+            // esync and fsync are widely supported ntsync is not
+            (None, _) => *self != Self::NTSync,
+
+            (_, None) => true
+        };
+
+        system && runner
+    }
+
+    /// Get primitive which should be used
+    ///
+    /// `Auto` is resolved to the best supported primitive. Other values are kept
+    /// as is, but a warning is logged if they're not supported
+    pub fn resolve(&self, capabilities: &WineSyncCapabilities, runner: Option<&[String]>) -> Self {
+        if *self == Self::Auto {
+            return [Self::NTSync, Self::FSync, Self::ESync].into_iter()
+                .find(|sync| sync.is_supported(capabilities, runner))
+                .unwrap_or(Self::None);
+        }
+
+        if !self.is_supported(capabilities, runner) {
+            tracing::warn!("{self:?} is not supported by the system or runner: {capabilities:?}");
+        }
+
+        *self
+    }
+
     /// Get environment variables corresponding to used wine sync
+    ///
+    /// `Auto` should be resolved first, otherwise no variables are returned
     pub fn get_env_vars(&self) -> HashMap<&str, &str> {
-        let key = match self {
-            Self::None => return HashMap::new(),
+        let keys: &[&str] = match self {
+            Self::None | Self::Auto => return HashMap::new(),
 
-            Self::ESync => "WINEESYNC",
-            Self::FSync => "WINEFSYNC"
+            Self::ESync  => &["WINEESYNC"],
+            Self::FSync  => &["WINEFSYNC"],
+            Self::NTSync => &["WINENTSYNC", "PROTON_USE_NTSYNC"]
         };
 
-        HashMap::from([(key, "1")])
+        keys.iter()
+            .map(|key| (*key, "1"))
+            .collect()
     }
 }
@@ -0,0 +1,436 @@
+//! Game environment variables composition
+//!
+//! Environment variables come from many sources (runner and components features,
+//! enhancements, user config), so instead of applying them to the command
+//! one by one they're collected by the `EnvBuilder` which remembers the source
+//! of every variable and reports conflicts between them
+//!
+//! Use the `game::dry_run` function of every game to get
+//! the game's environment without running it
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::process::Command;
+
+/// Variables which contain list of paths separated by `:`
+///
+/// Values of these variables are merged instead of being overwritten,
+/// and the host's value is appended to the end of the list
+pub const PATH_LIST_VARS: &[&str] = &[
+    "PATH",
+    "LD_LIBRARY_PATH",
+    "GST_PLUGIN_PATH",
+    "GST_PLUGIN_SYSTEM_PATH",
+    "VK_ADD_LAYER_PATH",
+    "XDG_DATA_DIRS"
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnvSource {
+    /// Variables set by the launcher itself, e.g. `WINEPREFIX`
+    Launcher,
+
+    /// Selected runner variables (e.g. proton's `STEAM_COMPAT_DATA_PATH`)
+    Runner,
+
+    /// Selected wine features
+    Wine,
+
+    /// Selected dxvk features
+    Dxvk,
+
+    /// Selected vkd3d features
+    Vkd3d,
+
+    /// Per-game shader caches folders
+    ShaderCache,
+
+    Hud,
+    Fsr,
+    WineSync,
+    WineDebug,
+    WineLang,
+    DisplayBackend,
+    SharedLibraries,
+    DllOverrides,
+
+    /// Mods loaders
+    Mods,
+
+    /// Steam environment cleanup
+    Steam,
+
+    /// `game.environment` config field
+    User
+}
+
+impl std::fmt::Display for EnvSource {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{:?}", self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvValue {
+    /// Variable value. `None` if the variable is unset
+    pub value: Option<String>,
+
+    pub source: EnvSource
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvConflict {
+    pub key: String,
+
+    /// Value which was overwritten
+    pub previous: EnvValue,
+
+    /// Value which overwrote the previous one
+    pub current: EnvValue
+}
+
+impl std::fmt::Display for EnvConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = |value: &EnvValue| match &value.value {
+            Some(variable) => format!("\"{variable}\" ({})", value.source),
+            None => format!("unset ({})", value.source)
+        };
+
+        write!(f, "{}: {} overwritten by {}", self.key, format(&self.previous), format(&self.current))
+    }
+}
+
+#[inline]
+fn is_path_list(key: &str) -> bool {
+    PATH_LIST_VARS.contains(&key)
+}
+
+/// Merge paths lists. Paths from the `higher` list go first
+fn merge_paths(higher: &str, lower: &str) -> String {
+    let mut paths = Vec::new();
+
+    for path in higher.split(':').chain(lower.split(':')) {
+        if !path.is_empty() && !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    paths.join(":")
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EnvBuilder {
+    vars: BTreeMap<String, EnvValue>,
+
+    /// Path list variables which don't inherit the host's value
+    isolated: HashSet<String>,
+
+    conflicts: Vec<EnvConflict>
+}
+
+impl EnvBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&mut self, key: String, current: EnvValue) {
+        if let Some(previous) = self.vars.get(&key) {
+            if previous.value != current.value && previous.source != current.source {
+                self.conflicts.push(EnvConflict {
+                    key: key.clone(),
+                    previous: previous.clone(),
+                    current: current.clone()
+                });
+            }
+        }
+
+        self.vars.insert(key, current);
+    }
+
+    /// Set variable value
+    ///
+    /// Path list variables are merged with the already set value
+    pub fn set(&mut self, key: impl ToString, value: impl ToString, source: EnvSource) -> &mut Self {
+        let key = key.to_string();
+        let mut value = value.to_string();
+
+        if is_path_list(&key) {
+            if let Some(EnvValue { value: Some(previous), .. }) = self.vars.get(&key) {
+                value = merge_paths(&value, previous);
+            }
+
+            // Merging path lists is not a conflict
+            self.vars.insert(key, EnvValue {
+                value: Some(value),
+                source
+            });
+
+            return self;
+        }
+
+        self.insert(key, EnvValue {
+            value: Some(value),
+            source
+        });
+
+        self
+    }
+
+    /// Set variables values
+    pub fn extend<K: ToString, V: ToString>(&mut self, vars: impl IntoIterator<Item = (K, V)>, source: EnvSource) -> &mut Self {
+        for (key, value) in vars {
+            self.set(key, value, source);
+        }
+
+        self
+    }
+
+    /// Set variable value replacing the current one and the host's value
+    /// even if it's a path list variable
+    pub fn replace(&mut self, key: impl ToString, value: impl ToString, source: EnvSource) -> &mut Self {
+        let key = key.to_string();
+
+        self.isolated.insert(key.clone());
+
+        self.insert(key, EnvValue {
+            value: Some(value.to_string()),
+            source
+        });
+
+        self
+    }
+
+    /// Unset variable, so it's not inherited from the host either
+    pub fn unset(&mut self, key: impl ToString, source: EnvSource) -> &mut Self {
+        self.insert(key.to_string(), EnvValue {
+            value: None,
+            source
+        });
+
+        self
+    }
+
+    /// Don't inherit the host's value of given path list variable
+    ///
+    /// If the variable is not set yet, it's unset so the host's value
+    /// is not inherited by the command either
+    pub fn isolate(&mut self, key: impl ToString, source: EnvSource) -> &mut Self {
+        let key = key.to_string();
+
+        if !self.vars.contains_key(&key) {
+            self.vars.insert(key.clone(), EnvValue {
+                value: None,
+                source
+            });
+        }
+
+        self.isolated.insert(key);
+
+        self
+    }
+
+    /// Apply user environment variables
+    ///
+    /// User variables replace any other values. Special key prefixes are supported:
+    ///
+    /// - `-KEY` unsets the variable
+    /// - `+KEY` merges the value into the path list variable instead of replacing it
+    pub fn user<K: AsRef<str>, V: ToString>(&mut self, vars: impl IntoIterator<Item = (K, V)>) -> &mut Self {
+        for (key, value) in vars {
+            let key = key.as_ref();
+
+            if let Some(key) = key.strip_prefix('-') {
+                self.unset(key, EnvSource::User);
+            }
+
+            else if let Some(key) = key.strip_prefix('+') {
+                self.set(key, value, EnvSource::User);
+            }
+
+            else {
+                self.replace(key, value, EnvSource::User);
+            }
+        }
+
+        self
+    }
+
+    #[inline]
+    /// Get variable value and its source
+    pub fn get(&self, key: impl AsRef<str>) -> Option<&EnvValue> {
+        self.vars.get(key.as_ref())
+    }
+
+    #[inline]
+    /// Iterate over all the set and unset variables
+    pub fn vars(&self) -> impl Iterator<Item = (&String, &EnvValue)> {
+        self.vars.iter()
+    }
+
+    #[inline]
+    /// Get list of variables which were overwritten by another source
+    pub fn conflicts(&self) -> &[EnvConflict] {
+        &self.conflicts
+    }
+
+    /// Get final variables values including the host's values of the path list variables
+    ///
+    /// `None` means that the variable should be unset
+    pub fn build(&self) -> HashMap<String, Option<String>> {
+        self.vars.iter()
+            .map(|(key, var)| {
+                let value = var.value.as_ref().map(|value| {
+                    if !is_path_list(key) || self.isolated.contains(key) {
+                        return value.clone();
+                    }
+
+                    match std::env::var(key) {
+                        Ok(host) => merge_paths(value, &host),
+                        Err(_) => merge_paths(value, "")
+                    }
+                });
+
+                (key.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Apply variables to the command
+    pub fn apply(&self, command: &mut Command) {
+        for (key, value) in self.build() {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key)
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(env: &EnvBuilder, key: &str) -> Option<String> {
+        env.build().get(key).cloned().flatten()
+    }
+
+    #[test]
+    fn merge_paths_lists() {
+        assert_eq!(merge_paths("/a:/b", "/b:/c"), "/a:/b:/c");
+        assert_eq!(merge_paths("/a::", ":/a"), "/a");
+        assert_eq!(merge_paths("", ""), "");
+    }
+
+    #[test]
+    fn set_path_list() {
+        let mut env = EnvBuilder::new();
+
+        env.set("VK_ADD_LAYER_PATH", "/runner", EnvSource::Runner);
+        env.set("VK_ADD_LAYER_PATH", "/hud:/runner", EnvSource::Hud);
+
+        let var = env.get("VK_ADD_LAYER_PATH").unwrap();
+
+        // Later values go first
+        assert_eq!(var.value.as_deref(), Some("/hud:/runner"));
+        assert_eq!(var.source, EnvSource::Hud);
+
+        // Merging path lists is not a conflict
+        assert!(env.conflicts().is_empty());
+    }
+
+    #[test]
+    fn host_path_list() {
+        std::env::set_var("GST_PLUGIN_SYSTEM_PATH", "/host:/shared");
+
+        let mut env = EnvBuilder::new();
+
+        env.set("GST_PLUGIN_SYSTEM_PATH", "/shared:/runner", EnvSource::Runner);
+
+        assert_eq!(value(&env, "GST_PLUGIN_SYSTEM_PATH").as_deref(), Some("/shared:/runner:/host"));
+
+        env.replace("GST_PLUGIN_SYSTEM_PATH", "/user", EnvSource::User);
+
+        assert_eq!(value(&env, "GST_PLUGIN_SYSTEM_PATH").as_deref(), Some("/user"));
+    }
+
+    #[test]
+    fn replace_and_isolate() {
+        let mut env = EnvBuilder::new();
+
+        env.set("PATH", "/runner/bin", EnvSource::Runner);
+        env.replace("PATH", "/user/bin", EnvSource::User);
+
+        assert_eq!(value(&env, "PATH").as_deref(), Some("/user/bin"));
+
+        // Isolated variable keeps its value and doesn't inherit the host's one
+        env.set("LD_LIBRARY_PATH", "/runner/lib", EnvSource::Runner);
+        env.isolate("LD_LIBRARY_PATH", EnvSource::Steam);
+
+        assert_eq!(value(&env, "LD_LIBRARY_PATH").as_deref(), Some("/runner/lib"));
+        assert_eq!(env.get("LD_LIBRARY_PATH").unwrap().source, EnvSource::Runner);
+
+        // Not set variable is unset
+        env.isolate("XDG_DATA_DIRS", EnvSource::Steam);
+
+        assert_eq!(env.build().get("XDG_DATA_DIRS"), Some(&None));
+    }
+
+    #[test]
+    fn user_prefixes() {
+        let mut env = EnvBuilder::new();
+
+        env.set("DXVK_HUD", "fps", EnvSource::Hud);
+        env.set("WINEDEBUG", "-all", EnvSource::WineDebug);
+        env.set("VK_ADD_LAYER_PATH", "/runner", EnvSource::Runner);
+        env.set("XDG_DATA_DIRS", "/runner", EnvSource::Runner);
+
+        env.user([
+            ("DXVK_HUD", "full"),
+            ("-WINEDEBUG", ""),
+            ("+VK_ADD_LAYER_PATH", "/user"),
+            ("XDG_DATA_DIRS", "/user")
+        ]);
+
+        assert_eq!(env.get("DXVK_HUD"), Some(&EnvValue {
+            value: Some(String::from("full")),
+            source: EnvSource::User
+        }));
+
+        assert_eq!(env.get("WINEDEBUG"), Some(&EnvValue {
+            value: None,
+            source: EnvSource::User
+        }));
+
+        assert_eq!(env.build().get("WINEDEBUG"), Some(&None));
+
+        // +KEY merges the path list, plain KEY replaces it
+        assert_eq!(env.get("VK_ADD_LAYER_PATH").unwrap().value.as_deref(), Some("/user:/runner"));
+        assert_eq!(value(&env, "XDG_DATA_DIRS").as_deref(), Some("/user"));
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut env = EnvBuilder::new();
+
+        env.set("WINEESYNC", "1", EnvSource::WineSync);
+
+        // Same value or same source is not a conflict
+        env.set("WINEESYNC", "1", EnvSource::Runner);
+        env.set("WINEESYNC", "0", EnvSource::Runner);
+
+        assert!(env.conflicts().is_empty());
+
+        env.set("WINEESYNC", "1", EnvSource::Mods);
+        env.unset("WINEESYNC", EnvSource::User);
+
+        let conflicts = env.conflicts();
+
+        assert_eq!(conflicts.len(), 2);
+
+        assert_eq!(conflicts[0].previous.source, EnvSource::Runner);
+        assert_eq!(conflicts[0].current.value.as_deref(), Some("1"));
+
+        assert_eq!(conflicts[1].to_string(), "WINEESYNC: \"1\" (Mods) overwritten by unset (User)");
+    }
+}
@@ -0,0 +1,455 @@
+//! Frametime statistics from the MangoHud logs
+//!
+//! Every launch with enabled statistics gets its own folder with MangoHud
+//! CSV logs and the `record.json` file which stores runner and dxvk versions
+//! used for this launch and the frametimes summary calculated after the game exit
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+use crate::config::schema_blanks::prelude::{HUD, HudPreset, MangoHud};
+
+/// Name of the launch record file in the launch folder
+const RECORD_FILE: &str = "record.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FrametimeSummary {
+    /// Number of logged frames
+    pub frames: u64,
+
+    /// Total logged time in seconds
+    pub duration: f64,
+
+    pub average_fps: f64,
+
+    /// Average framerate of the 1% slowest frames
+    pub low_1: f64,
+
+    /// Average framerate of the 0.1% slowest frames
+    pub low_01: f64,
+
+    /// Frametimes percentiles in milliseconds
+    pub frametime_p50: f64,
+    pub frametime_p90: f64,
+    pub frametime_p95: f64,
+    pub frametime_p99: f64
+}
+
+impl FrametimeSummary {
+    /// Calculate summary from the frametimes in milliseconds
+    ///
+    /// Returns `None` if there are no frametimes
+    pub fn from_frametimes(frametimes: &[f64]) -> Option<Self> {
+        let mut frametimes = frametimes.iter()
+            .copied()
+            .filter(|frametime| frametime.is_finite() && *frametime > 0.0)
+            .collect::<Vec<_>>();
+
+        if frametimes.is_empty() {
+            return None;
+        }
+
+        frametimes.sort_by(f64::total_cmp);
+
+        let total = frametimes.iter().sum::<f64>();
+
+        let percentile = |percent: f64| {
+            let index = ((frametimes.len() - 1) as f64 * percent / 100.0).round() as usize;
+
+            frametimes[index]
+        };
+
+        // Average framerate of the slowest frames
+        let low = |percent: f64| {
+            let count = ((frametimes.len() as f64 * percent / 100.0).ceil() as usize).max(1);
+
+            let slowest = &frametimes[frametimes.len() - count..];
+
+            1000.0 * count as f64 / slowest.iter().sum::<f64>()
+        };
+
+        Some(Self {
+            frames: frametimes.len() as u64,
+            duration: total / 1000.0,
+            average_fps: 1000.0 * frametimes.len() as f64 / total,
+            low_1: low(1.0),
+            low_01: low(0.1),
+            frametime_p50: percentile(50.0),
+            frametime_p90: percentile(90.0),
+            frametime_p95: percentile(95.0),
+            frametime_p99: percentile(99.0)
+        })
+    }
+
+    /// Merge summaries of several launches
+    ///
+    /// Values are averaged proportionally to the launches durations,
+    /// so percentiles are approximate
+    pub fn merge(summaries: &[Self]) -> Option<Self> {
+        let duration = summaries.iter().map(|summary| summary.duration).sum::<f64>();
+
+        if duration <= 0.0 {
+            return None;
+        }
+
+        let average = |value: fn(&Self) -> f64| {
+            summaries.iter()
+                .map(|summary| value(summary) * summary.duration)
+                .sum::<f64>() / duration
+        };
+
+        Some(Self {
+            frames: summaries.iter().map(|summary| summary.frames).sum(),
+            duration,
+            average_fps: average(|summary| summary.average_fps),
+            low_1: average(|summary| summary.low_1),
+            low_01: average(|summary| summary.low_01),
+            frametime_p50: average(|summary| summary.frametime_p50),
+            frametime_p90: average(|summary| summary.frametime_p90),
+            frametime_p95: average(|summary| summary.frametime_p95),
+            frametime_p99: average(|summary| summary.frametime_p99)
+        })
+    }
+}
+
+impl std::fmt::Display for FrametimeSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1} fps average, {:.1} fps 1% low, {:.1} fps 0.1% low, {:.2} ms p99 frametime ({} frames)",
+            self.average_fps,
+            self.low_1,
+            self.low_01,
+            self.frametime_p99,
+            self.frames
+        )
+    }
+}
+
+/// Read frametimes in milliseconds from the MangoHud CSV log
+pub fn parse_log(path: impl AsRef<Path>) -> anyhow::Result<Vec<f64>> {
+    let log = std::fs::read_to_string(path)?;
+
+    let mut lines = log.lines();
+
+    // Logs start with the system info block, so we skip lines
+    // until the frames table header
+    let column = lines.by_ref()
+        .find_map(|line| {
+            let columns = line.split(',').map(str::trim).collect::<Vec<_>>();
+
+            if columns.first() == Some(&"fps") {
+                columns.iter().position(|column| *column == "frametime")
+            } else {
+                None
+            }
+        })
+        .ok_or_else(|| anyhow::anyhow!("Frames table is not found in the MangoHud log"))?;
+
+    Ok(lines.filter_map(|line| line.split(',').nth(column)?.trim().parse().ok()).collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchRecord {
+    /// Folder with the MangoHud logs and record file
+    pub folder: PathBuf,
+
+    /// UTC timestamp
+    pub started_at: u64,
+
+    /// UTC timestamp. `None` if the game is still running
+    pub finished_at: Option<u64>,
+
+    /// Selected runner name
+    pub runner: String,
+
+    /// Selected dxvk name
+    pub dxvk: Option<String>,
+
+    pub game_version: Option<String>,
+
+    /// `None` if there were no logged frames
+    pub summary: Option<FrametimeSummary>
+}
+
+#[inline]
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+impl LaunchRecord {
+    /// Create new launch folder in the given one
+    pub fn start(root: impl AsRef<Path>, runner: impl ToString, dxvk: Option<String>, game_version: Option<String>) -> anyhow::Result<Self> {
+        let started_at = now();
+
+        let mut folder = root.as_ref().join(started_at.to_string());
+        let mut index = 1;
+
+        while folder.exists() {
+            folder = root.as_ref().join(format!("{started_at}-{index}"));
+
+            index += 1;
+        }
+
+        std::fs::create_dir_all(&folder)?;
+
+        let record = Self {
+            folder,
+            started_at,
+            finished_at: None,
+            runner: runner.to_string(),
+            dxvk,
+            game_version,
+            summary: None
+        };
+
+        record.save()?;
+
+        Ok(record)
+    }
+
+    /// Read launch record from the launch folder
+    pub fn from_folder(folder: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut record: Self = serde_json::from_slice(&std::fs::read(folder.as_ref().join(RECORD_FILE))?)?;
+
+        // Folder could be moved
+        record.folder = folder.as_ref().to_path_buf();
+
+        Ok(record)
+    }
+
+    #[inline]
+    /// Write the record file
+    pub fn save(&self) -> anyhow::Result<()> {
+        Ok(std::fs::write(self.folder.join(RECORD_FILE), serde_json::to_string_pretty(self)?)?)
+    }
+
+    /// Make mangohud log every frame to the launch folder
+    ///
+    /// If the hud is not MangoHud, it's replaced by the hidden one
+    pub fn configure_hud(&self, hud: &mut HUD, mangohud: &mut MangoHud) {
+        if *hud == HUD::DXVK {
+            tracing::warn!("DXVK HUD is replaced by the hidden MangoHud to log frametimes");
+        }
+
+        if *hud != HUD::MangoHUD {
+            *hud = HUD::MangoHUD;
+
+            *mangohud = MangoHud {
+                preset: HudPreset::Custom,
+                hidden: true,
+                ..MangoHud::default()
+            };
+        }
+
+        mangohud.log_folder = Some(self.folder.clone());
+        mangohud.autostart_log = true;
+    }
+
+    /// Calculate frametimes summary from the logs and save the record
+    pub fn finish(&mut self) -> anyhow::Result<()> {
+        let mut frametimes = Vec::new();
+
+        for entry in std::fs::read_dir(&self.folder)?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            // Newer MangoHud versions write their own summary files
+            if !name.ends_with(".csv") || name.ends_with("_summary.csv") {
+                continue;
+            }
+
+            match parse_log(entry.path()) {
+                Ok(log) => frametimes.extend(log),
+                Err(err) => tracing::warn!("Failed to parse MangoHud log {name}: {err}")
+            }
+        }
+
+        self.finished_at = Some(now());
+        self.summary = FrametimeSummary::from_frametimes(&frametimes);
+
+        self.save()
+    }
+}
+
+/// List launch records from the given folder, oldest first
+pub fn list(root: impl AsRef<Path>) -> anyhow::Result<Vec<LaunchRecord>> {
+    let root = root.as_ref();
+
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut records = Vec::new();
+
+    for entry in std::fs::read_dir(root)?.flatten() {
+        if entry.path().join(RECORD_FILE).exists() {
+            match LaunchRecord::from_folder(entry.path()) {
+                Ok(record) => records.push(record),
+                Err(err) => tracing::warn!("Failed to read launch record {:?}: {err}", entry.path())
+            }
+        }
+    }
+
+    records.sort_by_key(|record| record.started_at);
+
+    Ok(records)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionsSummary {
+    pub runner: String,
+    pub dxvk: Option<String>,
+
+    /// Number of launches with logged frames
+    pub launches: usize,
+
+    pub summary: FrametimeSummary
+}
+
+/// Compare frametimes summaries of different runner and dxvk versions
+///
+/// Returns merged summaries of every runner and dxvk pair sorted by the average framerate
+pub fn compare(records: &[LaunchRecord]) -> Vec<VersionsSummary> {
+    let mut groups = BTreeMap::<(String, Option<String>), Vec<FrametimeSummary>>::new();
+
+    for record in records {
+        if let Some(summary) = record.summary {
+            groups.entry((record.runner.clone(), record.dxvk.clone()))
+                .or_default()
+                .push(summary);
+        }
+    }
+
+    let mut summaries = groups.into_iter()
+        .filter_map(|((runner, dxvk), summaries)| {
+            Some(VersionsSummary {
+                runner,
+                dxvk,
+                launches: summaries.len(),
+                summary: FrametimeSummary::merge(&summaries)?
+            })
+        })
+        .collect::<Vec<_>>();
+
+    summaries.sort_by(|a, b| b.summary.average_fps.total_cmp(&a.summary.average_fps));
+
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+os,cpu,gpu,ram,kernel,driver,cpuscheduler
+Arch Linux,AMD Ryzen 7 5800X,AMD Radeon RX 6800,32 GB,6.9.1,Mesa 24.1.1,schedutil
+fps,frametime,cpu_load,gpu_load,cpu_temp,gpu_temp,elapsed
+60.0,16.6,30,90,55,70,16600000
+50.0,20.0,31,91,55,70,36600000
+100.0,10.0,32,92,56,71,46600000
+broken line
+";
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn parse_mangohud_log() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("frametimes-test-{}.csv", std::process::id()));
+
+        std::fs::write(&path, LOG)?;
+
+        let frametimes = parse_log(&path);
+
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(frametimes?, vec![16.6, 20.0, 10.0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_log_without_frames_table() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("frametimes-test-empty-{}.csv", std::process::id()));
+
+        std::fs::write(&path, "os,cpu,gpu\nArch Linux,AMD,AMD\n")?;
+
+        let frametimes = parse_log(&path);
+
+        std::fs::remove_file(&path)?;
+
+        assert!(frametimes.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn summary() {
+        let frametimes = (1..=100).rev().map(f64::from).collect::<Vec<_>>();
+
+        let summary = FrametimeSummary::from_frametimes(&frametimes).unwrap();
+
+        assert_eq!(summary.frames, 100);
+
+        assert!(approx(summary.duration, 5.05));
+        assert!(approx(summary.average_fps, 100000.0 / 5050.0));
+
+        // The slowest frame takes 100 ms
+        assert!(approx(summary.low_1, 10.0));
+        assert!(approx(summary.low_01, 10.0));
+
+        assert!(approx(summary.frametime_p50, 51.0));
+        assert!(approx(summary.frametime_p90, 90.0));
+        assert!(approx(summary.frametime_p95, 95.0));
+        assert!(approx(summary.frametime_p99, 99.0));
+    }
+
+    #[test]
+    fn summary_lows() {
+        // 1% of 1000 frames are the 10 slowest ones
+        let mut frametimes = vec![10.0; 990];
+
+        frametimes.extend([50.0; 10]);
+
+        let summary = FrametimeSummary::from_frametimes(&frametimes).unwrap();
+
+        assert!(approx(summary.low_1, 20.0));
+        assert!(approx(summary.low_01, 20.0));
+        assert!(approx(summary.frametime_p50, 10.0));
+    }
+
+    #[test]
+    fn summary_of_invalid_frametimes() {
+        assert_eq!(FrametimeSummary::from_frametimes(&[]), None);
+        assert_eq!(FrametimeSummary::from_frametimes(&[0.0, -1.0, f64::NAN, f64::INFINITY]), None);
+
+        let summary = FrametimeSummary::from_frametimes(&[f64::NAN, 20.0]).unwrap();
+
+        assert_eq!(summary.frames, 1);
+        assert!(approx(summary.average_fps, 50.0));
+    }
+
+    #[test]
+    fn merge_summaries() {
+        let fast = FrametimeSummary::from_frametimes(&[10.0; 100]).unwrap();
+        let slow = FrametimeSummary::from_frametimes(&[20.0; 150]).unwrap();
+
+        let merged = FrametimeSummary::merge(&[fast, slow]).unwrap();
+
+        assert_eq!(merged.frames, 250);
+
+        // 1 second of 100 fps and 3 seconds of 50 fps
+        assert!(approx(merged.duration, 4.0));
+        assert!(approx(merged.average_fps, 62.5));
+        assert!(approx(merged.frametime_p99, 17.5));
+
+        assert_eq!(FrametimeSummary::merge(&[]), None);
+    }
+}
@@ -11,6 +11,11 @@ pub struct Enhancements {
     pub fsr: Fsr,
     pub gamemode: bool,
     pub hud: HUD,
+    pub dxvk_hud: DxvkHud,
+    pub mangohud: MangoHud,
+
+    /// Log frametimes of every launch with MangoHud
+    pub frametime_stats: bool,
 
     #[cfg(feature = "fps-unlocker")]
     pub fps_unlocker: FpsUnlocker,
@@ -35,6 +40,18 @@ impl From<&JsonValue> for Enhancements {
                 .map(HUD::from)
                 .unwrap_or(default.hud),
 
+            dxvk_hud: value.get("dxvk_hud")
+                .map(DxvkHud::from)
+                .unwrap_or(default.dxvk_hud),
+
+            mangohud: value.get("mangohud")
+                .map(MangoHud::from)
+                .unwrap_or(default.mangohud),
+
+            frametime_stats: value.get("frametime_stats")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.frametime_stats),
+
             #[cfg(feature = "fps-unlocker")]
             fps_unlocker: value.get("fps_unlocker")
                 .map(FpsUnlocker::from)
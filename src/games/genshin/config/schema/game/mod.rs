@@ -9,6 +9,7 @@ use crate::genshin::consts::launcher_dir;
 
 crate::config_impl_wine_schema!(launcher_dir);
 crate::config_impl_dxvk_schema!(launcher_dir);
+crate::config_impl_vkd3d_schema!(launcher_dir);
 
 pub mod paths;
 pub mod enhancements;
@@ -19,6 +20,7 @@ pub mod fps_unlocker;
 pub mod prelude {
     pub use super::Wine;
     pub use super::Dxvk;
+    pub use super::Vkd3d;
 
     #[cfg(feature = "fps-unlocker")]
     pub use super::fps_unlocker::prelude::*;
@@ -38,6 +40,9 @@ pub struct Game {
     pub voices: Vec<String>,
     pub wine: Wine,
     pub dxvk: Dxvk,
+    pub vkd3d: Vkd3d,
+    pub shader_cache: ShaderCacheConfig,
+    pub display: DisplayConfig,
     pub enhancements: Enhancements,
     pub environment: HashMap<String, String>,
     pub command: Option<String>
@@ -53,6 +58,9 @@ impl Default for Game {
             ],
             wine: Wine::default(),
             dxvk: Dxvk::default(),
+            vkd3d: Vkd3d::default(),
+            shader_cache: ShaderCacheConfig::default(),
+            display: DisplayConfig::default(),
             enhancements: Enhancements::default(),
             environment: HashMap::new(),
             command: None
@@ -95,6 +103,18 @@ impl From<&JsonValue> for Game {
                 .map(Dxvk::from)
                 .unwrap_or(default.dxvk),
 
+            vkd3d: value.get("vkd3d")
+                .map(Vkd3d::from)
+                .unwrap_or(default.vkd3d),
+
+            shader_cache: value.get("shader_cache")
+                .map(ShaderCacheConfig::from)
+                .unwrap_or(default.shader_cache),
+
+            display: value.get("display")
+                .map(DisplayConfig::from)
+                .unwrap_or(default.display),
+
             enhancements: value.get("enhancements")
                 .map(Enhancements::from)
                 .unwrap_or(default.enhancements),
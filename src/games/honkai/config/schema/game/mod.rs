@@ -9,6 +9,7 @@ use crate::honkai::consts::launcher_dir;
 
 crate::config_impl_wine_schema!(launcher_dir);
 crate::config_impl_dxvk_schema!(launcher_dir);
+crate::config_impl_vkd3d_schema!(launcher_dir);
 
 pub mod paths;
 pub mod enhancements;
@@ -16,6 +17,7 @@ pub mod enhancements;
 pub mod prelude {
     pub use super::Wine;
     pub use super::Dxvk;
+    pub use super::Vkd3d;
     pub use super::paths::Paths;
     pub use super::enhancements::Enhancements;
 }
@@ -27,6 +29,9 @@ pub struct Game {
     pub path: Paths,
     pub wine: Wine,
     pub dxvk: Dxvk,
+    pub vkd3d: Vkd3d,
+    pub shader_cache: ShaderCacheConfig,
+    pub display: DisplayConfig,
     pub enhancements: Enhancements,
     pub environment: HashMap<String, String>,
     pub command: Option<String>
@@ -39,6 +44,9 @@ impl Default for Game {
             path: Paths::default(),
             wine: Wine::default(),
             dxvk: Dxvk::default(),
+            vkd3d: Vkd3d::default(),
+            shader_cache: ShaderCacheConfig::default(),
+            display: DisplayConfig::default(),
             enhancements: Enhancements::default(),
             environment: HashMap::new(),
             command: None
@@ -63,6 +71,18 @@ impl From<&JsonValue> for Game {
                 .map(Dxvk::from)
                 .unwrap_or(default.dxvk),
 
+            vkd3d: value.get("vkd3d")
+                .map(Vkd3d::from)
+                .unwrap_or(default.vkd3d),
+
+            shader_cache: value.get("shader_cache")
+                .map(ShaderCacheConfig::from)
+                .unwrap_or(default.shader_cache),
+
+            display: value.get("display")
+                .map(DisplayConfig::from)
+                .unwrap_or(default.display),
+
             enhancements: value.get("enhancements")
                 .map(Enhancements::from)
                 .unwrap_or(default.enhancements),
@@ -7,8 +7,16 @@ use crate::sessions::{
     Sessions as SessionsDescriptor
 };
 
+use crate::registry::Registry;
+
 use super::consts::launcher_dir;
 
+/// Registry key with the game's settings and account data
+pub const GAME_REG_KEY: &str = "Software\\miHoYo\\Honkai Impact 3rd";
+
+/// Registry key with the game's SDK data
+pub const SDK_REG_KEY: &str = "Software\\miHoYoSDK";
+
 /// Get default sessions file path
 /// 
 /// `$HOME/.local/share/honkers-launcher/sessions.json`
@@ -57,14 +65,14 @@ impl SessionsExt for Sessions {
             sdk_reg: String::new()
         };
 
-        for entry in std::fs::read_to_string(prefix.as_ref().join("user.reg"))?.split("\n\n") {
-            if entry.starts_with("[Software\\\\miHoYo\\\\Honkai Impact 3rd]") {
-                new_session.game_reg = entry.to_owned();
-            }
+        let registry = Registry::from_file(prefix.as_ref().join("user.reg"))?;
 
-            else if entry.starts_with("[Software\\\\miHoYoSDK]") {
-                new_session.sdk_reg = entry.to_owned();
-            }
+        if let Some(key) = registry.get_key(GAME_REG_KEY) {
+            new_session.game_reg = key.to_string();
+        }
+
+        if let Some(key) = registry.get_key(SDK_REG_KEY) {
+            new_session.sdk_reg = key.to_string();
         }
 
         sessions.sessions.insert(name, new_session);
@@ -81,25 +89,25 @@ impl SessionsExt for Sessions {
 
         tracing::info!("Applying session '{name}' to prefix: {:?}", prefix.as_ref());
 
-        let entries: String = std::fs::read_to_string(prefix.as_ref().join("user.reg"))?
-            .split("\n\n")
-            .map(|entry| {
-                let new_entry = if entry.starts_with("[Software\\\\miHoYo\\\\Honkai Impact 3rd]") {
-                    session.game_reg.clone()
-                }
-    
-                else if entry.starts_with("[Software\\\\miHoYoSDK]") {
-                    session.sdk_reg.clone()
-                }
+        let path = prefix.as_ref().join("user.reg");
+
+        let mut registry = Registry::from_file(&path)?;
 
-                else {
-                    entry.to_owned()
-                };
+        for (key, block) in [(GAME_REG_KEY, &session.game_reg), (SDK_REG_KEY, &session.sdk_reg)] {
+            // Sessions store raw registry blocks so we parse them as a registry file
+            let session_key = Registry::parse(block)?
+                .keys
+                .into_iter()
+                .find(|session_key| session_key.is(key));
 
-                new_entry + "\n\n"
-            })
-            .collect();
+            match session_key {
+                Some(session_key) => registry.insert_key(session_key),
+                None => {
+                    registry.remove_key(key);
+                }
+            }
+        }
 
-        Ok(std::fs::write(prefix.as_ref().join("user.reg"), format!("{}\n", entries.trim_end()))?)
+        registry.write(path)
     }
 }
@@ -9,6 +9,7 @@ use crate::star_rail::consts::launcher_dir;
 
 crate::config_impl_wine_schema!(launcher_dir);
 crate::config_impl_dxvk_schema!(launcher_dir);
+crate::config_impl_vkd3d_schema!(launcher_dir);
 
 pub mod enhancements;
 pub mod paths;
@@ -16,6 +17,7 @@ pub mod paths;
 pub mod prelude {
     pub use super::Wine;
     pub use super::Dxvk;
+    pub use super::Vkd3d;
 
     pub use super::enhancements::Enhancements;
     pub use super::paths::Paths;
@@ -29,6 +31,9 @@ pub struct Game {
     pub voices: Vec<String>,
     pub wine: Wine,
     pub dxvk: Dxvk,
+    pub vkd3d: Vkd3d,
+    pub shader_cache: ShaderCacheConfig,
+    pub display: DisplayConfig,
     pub enhancements: Enhancements,
     pub environment: HashMap<String, String>,
     pub command: Option<String>
@@ -44,6 +49,9 @@ impl Default for Game {
             ],
             wine: Wine::default(),
             dxvk: Dxvk::default(),
+            vkd3d: Vkd3d::default(),
+            shader_cache: ShaderCacheConfig::default(),
+            display: DisplayConfig::default(),
             enhancements: Enhancements::default(),
             environment: HashMap::new(),
             command: None
@@ -86,6 +94,18 @@ impl From<&JsonValue> for Game {
                 .map(Dxvk::from)
                 .unwrap_or(default.dxvk),
 
+            vkd3d: value.get("vkd3d")
+                .map(Vkd3d::from)
+                .unwrap_or(default.vkd3d),
+
+            shader_cache: value.get("shader_cache")
+                .map(ShaderCacheConfig::from)
+                .unwrap_or(default.shader_cache),
+
+            display: value.get("display")
+                .map(DisplayConfig::from)
+                .unwrap_or(default.display),
+
             enhancements: value.get("enhancements")
                 .map(Enhancements::from)
                 .unwrap_or(default.enhancements),
@@ -5,14 +5,26 @@ use std::fs::File;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use anime_game_core::prelude::*;
 use anime_game_core::star_rail::telemetry;
+use anime_game_core::star_rail::game::Game;
 
 use crate::config::ConfigExt;
+use crate::env::{EnvBuilder, EnvSource};
+use crate::shader_cache::ShaderCache;
+use crate::frametimes::LaunchRecord;
 use crate::star_rail::config::Config;
 
 use crate::config::schema_blanks::prelude::{
     WineDrives,
-    AllowedDrives
+    AllowedDrives,
+    WineDebug,
+    WineSyncCapabilities,
+    DllOverrides,
+    DxvkOptions,
+    WindowMode,
+    HUD,
+    GamescopeCapabilities
 };
 
 use crate::star_rail::consts;
@@ -47,46 +59,178 @@ fn replace_keywords(command: impl ToString, folders: &Folders) -> String {
 /// This function will freeze thread it was called from while the game is running
 #[tracing::instrument(level = "info", ret)]
 pub fn run() -> anyhow::Result<()> {
+    launch(None, false)?;
+
+    Ok(())
+}
+
+/// Try to run the game with wine debug tracing
+///
+/// Given debug channels (e.g. `+seh,+loaddll`) are traced to the `game.trace.log`
+/// file next to the `game.log` without changing the config. Returns path to the trace file
+///
+/// This function will freeze thread it was called from while the game is running
+#[tracing::instrument(level = "info", ret)]
+pub fn run_with_trace(channels: &str) -> anyhow::Result<PathBuf> {
+    let trace_file = consts::launcher_dir()?.join("game.trace.log");
+
+    launch(Some(WineDebug::trace(channels, trace_file.clone())), false)?;
+
+    Ok(trace_file)
+}
+
+/// Prepare the game's environment variables without running the game
+///
+/// Steps which modify the wine prefix, game folder or launcher files are skipped.
+/// Use `EnvBuilder::conflicts` to get the variables overwritten by other sources
+#[tracing::instrument(level = "info")]
+pub fn dry_run() -> anyhow::Result<EnvBuilder> {
+    launch(None, true)
+}
+
+fn launch(debug: Option<WineDebug>, dry_run: bool) -> anyhow::Result<EnvBuilder> {
     tracing::info!("Preparing to run the game");
 
     let config = Config::get()?;
+
+    // One-shot debug settings replace the configured ones
+    let wine_debug = debug.unwrap_or_else(|| config.game.wine.debug.clone());
     let game_path = config.game.path.for_edition(config.launcher.edition).to_path_buf();
 
     if !game_path.exists() {
         return Err(anyhow::anyhow!("Game is not installed"));
     }
 
-    let Some(wine) = config.get_selected_wine()? else {
+    let Some(runner) = config.get_selected_runner()? else {
         anyhow::bail!("Couldn't find wine executable");
     };
 
-    let features = wine.features(&config.components.path)?.unwrap_or_default();
+    let wine = runner.to_version();
+    let features = runner.features(&config.components.path)?.unwrap_or_default();
 
     let mut folders = Folders {
-        wine: config.game.wine.builds.join(&wine.name),
+        wine: runner.folder().unwrap_or_else(|| config.game.wine.build_folder(&wine.name)),
         prefix: config.game.wine.prefix.clone(),
         game: game_path.clone(),
         patch: config.patch.path.clone(),
         temp: config.launcher.temp.clone().unwrap_or(std::env::temp_dir())
     };
 
+    if !dry_run {
+        // Remember when the runner was used last time. Runners stored outside
+        // of the builds folder (e.g. Steam's proton) are not managed by the launcher
+        if runner.has_files() && folders.wine.starts_with(&config.game.wine.builds) {
+            if let Err(err) = crate::components::gc::mark_used(&folders.wine) {
+                tracing::warn!("Failed to mark runner as used: {err}");
+            }
+        }
+
+        // Snapshot the prefix if the runner was changed since the last launch
+        match crate::prefix::snapshots::track_runner(&config.game.wine.prefix, runner.name(), config.game.wine.auto_snapshot) {
+            Ok(Some(snapshot)) => tracing::info!("Prefix snapshot taken before the runner change: {}", snapshot.name),
+            Ok(None) => (),
+
+            Err(err) => tracing::warn!("Failed to track prefix runner: {err}")
+        }
+    }
+
+    // Apply prefix tweaks
+    if !dry_run && !config.game.wine.tweaks.is_empty() {
+        if !runner.has_files() {
+            tracing::warn!("Prefix tweaks are not applied because umu has no selected proton build");
+        }
+
+        else {
+            let prefix_wine = wine.to_wine(config.components.path.clone(), Some(folders.wine.clone()))
+                .with_prefix(&config.game.wine.prefix);
+
+            if let Err(err) = crate::prefix::tweaks::ensure(&prefix_wine, &config.game.wine.tweaks_manifest, &config.game.wine.tweaks) {
+                tracing::warn!("Failed to apply prefix tweaks: {err}");
+            }
+        }
+    }
+
+    // Generate dxvk.conf in the game folder
+    let dxvk_conf = folders.game.join("dxvk.conf");
+
+    let dxvk_conf_generated = if config.game.dxvk.options.is_default() {
+        if !dry_run {
+            DxvkOptions::remove(&dxvk_conf)?;
+        }
+
+        false
+    }
+
+    else if dry_run {
+        DxvkOptions::is_writable(&dxvk_conf)?
+    }
+
+    else {
+        let generated = config.game.dxvk.options.write(&dxvk_conf)?;
+
+        if !generated {
+            tracing::warn!("dxvk.conf file is not generated because there's a user made one in the game folder");
+        }
+
+        generated
+    };
+
+    // Point shader caches to the per-runner folders
+    let shader_cache = if config.game.shader_cache.enabled {
+        let shader_cache = ShaderCache::new(consts::cache_dir()?, runner.name());
+
+        if !dry_run && config.game.shader_cache.auto_clear {
+            let game_version = Game::new(&game_path, config.launcher.edition).get_version();
+
+            let dxvk_version = config.get_selected_dxvk()
+                .ok()
+                .flatten()
+                .map(|dxvk| dxvk.name);
+
+            match game_version {
+                Ok(game_version) => match shader_cache.validate(game_version.to_string(), dxvk_version.as_deref()) {
+                    Ok(true) => tracing::info!("Shader caches cleared because of the game or dxvk version change"),
+                    Ok(false) => (),
+
+                    Err(err) => tracing::warn!("Failed to validate shader caches: {err}")
+                }
+
+                Err(err) => tracing::warn!("Failed to get game version to validate shader caches: {err}")
+            }
+        }
+
+        if !dry_run {
+            shader_cache.prepare()?;
+        }
+
+        Some(shader_cache)
+    }
+
+    else {
+        None
+    };
+
     // Check telemetry servers
 
-    tracing::info!("Checking telemetry");
+    if !dry_run {
+        tracing::info!("Checking telemetry");
 
-    if let Ok(Some(server)) = telemetry::is_disabled(config.launcher.edition) {
-        return Err(anyhow::anyhow!("Telemetry server is not disabled: {server}"));
+        if let Ok(Some(server)) = telemetry::is_disabled(config.launcher.edition) {
+            return Err(anyhow::anyhow!("Telemetry server is not disabled: {server}"));
+        }
     }
 
-    // Prepare wine prefix drives
-    config.game.wine.drives.map_folders(&folders.game, &config.game.wine.prefix)?;
+    if !dry_run {
+        // Prepare wine prefix drives
+        config.game.wine.drives.map_folders(&folders.game, &config.game.wine.prefix)?;
 
-    // Workaround for the jadeite patch (we run it from Z: drive)
-    WineDrives::map_folder(&config.game.wine.prefix, AllowedDrives::Z, "/")?;
+        // Workaround for the jadeite patch (we run it from Z: drive)
+        WineDrives::map_folder(&config.game.wine.prefix, AllowedDrives::Z, "/")?;
 
-    // Workaround for sandboxing feature
-    if config.sandbox.enabled {
-        WineDrives::map_folder(&config.game.wine.prefix, AllowedDrives::C, "../drive_c")?;
+        // Workaround for sandboxing feature
+        if config.sandbox.enabled {
+            WineDrives::map_folder(&config.game.wine.prefix, AllowedDrives::C, "../drive_c")?;
+        }
     }
 
     // Prepare bash -c '<command>'
@@ -114,8 +258,16 @@ pub fn run() -> anyhow::Result<()> {
 
     windows_command += &format!("'{}/jadeite.exe' 'Z:\\{}/StarRail.exe' -- ", folders.patch.to_string_lossy(), folders.game.to_string_lossy());
 
-    if config.game.wine.borderless {
-        launch_args += "-screen-fullscreen 0 -popupwindow ";
+    let mut display = config.game.display;
+
+    // Legacy borderless window option
+    if config.game.wine.borderless && display.window_mode == WindowMode::None {
+        display.window_mode = WindowMode::Popup;
+    }
+
+    for arg in display.get_launch_args() {
+        launch_args += &arg;
+        launch_args += " ";
     }
 
     // https://notabug.org/Krock/dawn/src/master/TWEAKS.md
@@ -123,8 +275,57 @@ pub fn run() -> anyhow::Result<()> {
         launch_args += "-window-mode exclusive ";
     }
 
+    let mut hud = config.game.enhancements.hud;
+    let mut mangohud = config.game.enhancements.mangohud.clone();
+
+    // Log frametimes of this launch
+    let mut launch_record = None;
+
+    if !dry_run && config.game.enhancements.frametime_stats {
+        let game_version = Game::new(&game_path, config.launcher.edition)
+            .get_version()
+            .map(|version| version.to_string())
+            .ok();
+
+        let dxvk = config.get_selected_dxvk()
+            .ok()
+            .flatten()
+            .map(|dxvk| dxvk.name);
+
+        match LaunchRecord::start(consts::launcher_dir()?.join("frametimes"), runner.name(), dxvk, game_version) {
+            Ok(record) => {
+                record.configure_hud(&mut hud, &mut mangohud);
+
+                launch_record = Some(record);
+            }
+
+            Err(err) => tracing::warn!("Failed to start frametimes logging: {err}")
+        }
+    }
+
+    let mut gamescope = config.game.enhancements.gamescope.clone();
+
+    // Gamescope's mangoapp replaces the mangohud layer and uses its config
+    if gamescope.enabled && gamescope.options.mangohud && hud == HUD::None {
+        hud = HUD::MangoHUD;
+    }
+
+    // Mangohud is drawn by gamescope itself. Older gamescope versions
+    // don't have mangoapp, so the mangohud layer is used with them
+    let mangoapp = hud.uses_mangoapp(gamescope.enabled) && GamescopeCapabilities::supports("--mangoapp");
+
+    if mangoapp {
+        gamescope.options.mangohud = true;
+    }
+
+    else if hud.uses_mangoapp(gamescope.enabled) {
+        tracing::warn!("Installed gamescope doesn't support --mangoapp, so the MangoHud layer is used instead");
+
+        gamescope.options.mangohud = false;
+    }
+
     // gamescope <params> -- <command to run>
-    if let Some(gamescope) = config.game.enhancements.gamescope.get_command() {
+    if let Some(gamescope) = gamescope.get_checked_command()? {
         bash_command = format!("{gamescope} -- {bash_command}");
     }
 
@@ -132,11 +333,42 @@ pub fn run() -> anyhow::Result<()> {
     #[cfg(feature = "sandbox")]
     if config.sandbox.enabled {
         let bwrap = config.sandbox.get_command(
-            folders.wine.to_str().unwrap(),
+            runner.has_files().then(|| folders.wine.to_str().unwrap()),
             folders.prefix.to_str().unwrap(),
             folders.game.to_str().unwrap()
         );
 
+        #[cfg(feature = "sessions")]
+        let session = Sessions::get_current()?;
+
+        #[cfg(not(feature = "sessions"))]
+        let session: Option<String> = None;
+
+        // Spoofed machine id is tied to the active session
+        let machine_id = if dry_run {
+            None
+        } else {
+            config.sandbox.identity.prepare(consts::launcher_dir()?.join("sandbox"), session.as_deref())?
+        };
+
+        let mut bwrap = bwrap + &config.sandbox.identity.get_command(machine_id.as_deref());
+
+        // Proton's compat data folder links to the original prefix path
+        if let Some(compat_data) = runner.compat_data_path(&config.game.wine.prefix) {
+            bwrap += &format!(" --bind '{0}' '{0}'", compat_data.to_string_lossy());
+            bwrap += &format!(" --bind '{0}' '{0}'", config.game.wine.prefix.to_string_lossy());
+        }
+
+        // Shader caches are stored in the launcher's cache folder
+        if let Some(shader_cache) = &shader_cache {
+            bwrap += &format!(" --bind '{0}' '{0}'", shader_cache.folder.to_string_lossy());
+        }
+
+        // MangoHud writes frametimes logs to the launch record folder
+        if let Some(record) = &launch_record {
+            bwrap += &format!(" --bind '{0}' '{0}'", record.folder.to_string_lossy());
+        }
+
         let bwrap = format!("{bwrap} --bind '{}' /tmp/sandbox/patch", folders.patch.to_string_lossy());
 
         let sandboxed_folders = Folders {
@@ -185,33 +417,203 @@ pub fn run() -> anyhow::Result<()> {
 
     // Setup environment
 
-    command.env("WINEARCH", "win64");
-    command.env("WINEPREFIX", &folders.prefix);
+    let mut env = EnvBuilder::new();
+
+    env.set("WINEARCH", "win64", EnvSource::Launcher);
+    env.set("WINEPREFIX", folders.prefix.to_string_lossy(), EnvSource::Launcher);
+
+    // Dll overrides are collected from all the sources and merged at the end
+    let mut dll_overrides = DllOverrides::default();
 
     // Add environment flags for selected wine
     for (key, value) in features.env.into_iter() {
-        command.env(key, replace_keywords(value, &folders));
+        let value = replace_keywords(value, &folders);
+
+        if key == "WINEDLLOVERRIDES" {
+            dll_overrides.merge_env(value);
+        }
+
+        else {
+            env.set(key, value, EnvSource::Wine);
+        }
     }
 
+    // Prepare selected runner
+    if !dry_run {
+        runner.prepare(&config.game.wine.prefix)?;
+    }
+
+    env.extend(runner.get_env_vars(&config.game.wine.prefix, consts::UMU_GAME_ID), EnvSource::Runner);
+
     // Add environment flags for selected dxvk
     if let Ok(Some(dxvk)) = config.get_selected_dxvk() {
         if let Ok(Some(features)) = dxvk.features(&config.components.path) {
             for (key, value) in features.env.iter() {
-                command.env(key, replace_keywords(value, &folders));
+                let value = replace_keywords(value, &folders);
+
+                if key == "WINEDLLOVERRIDES" {
+                    dll_overrides.merge_env(value);
+                }
+
+                else {
+                    env.set(key, value, EnvSource::Dxvk);
+                }
+            }
+        }
+    }
+
+    if dxvk_conf_generated {
+        env.set("DXVK_CONFIG_FILE", folders.game.join("dxvk.conf").to_string_lossy(), EnvSource::Dxvk);
+    }
+
+    if let Some(shader_cache) = &shader_cache {
+        env.extend(shader_cache.get_env_vars(), EnvSource::ShaderCache);
+    }
+
+    // Add environment flags for selected vkd3d
+    if let Ok(Some(vkd3d)) = config.get_selected_vkd3d() {
+        if let Ok(Some(features)) = vkd3d.features(&config.components.path) {
+            for (key, value) in features.env.iter() {
+                let value = replace_keywords(value, &folders);
+
+                if key == "WINEDLLOVERRIDES" {
+                    dll_overrides.merge_env(value);
+                }
+
+                else {
+                    env.set(key, value, EnvSource::Vkd3d);
+                }
             }
         }
     }
 
     let wine_folder = folders.wine.clone();
 
-    command.envs(config.game.enhancements.hud.get_env_vars(config.game.enhancements.gamescope.enabled));
-    command.envs(config.game.enhancements.fsr.get_env_vars());
+    if !dry_run && hud == HUD::MangoHUD {
+        if let Err(err) = mangohud.prepare() {
+            tracing::warn!("Failed to prepare mangohud config: {err}");
+        }
+    }
+
+    let hud_vars = hud.get_env_vars(
+        &config.game.enhancements.dxvk_hud,
+        &mangohud,
+        mangoapp
+    );
+
+    env.extend(hud_vars, EnvSource::Hud);
+    env.extend(config.game.enhancements.fsr.get_env_vars(), EnvSource::Fsr);
+
+    let wine_sync = config.game.wine.sync.resolve(&WineSyncCapabilities::probe(), features.sync.as_deref());
 
-    command.envs(config.game.wine.sync.get_env_vars());
-    command.envs(config.game.wine.language.get_env_vars());
-    command.envs(config.game.wine.shared_libraries.get_env_vars(wine_folder));
+    tracing::debug!("Using wine sync: {wine_sync:?}");
+
+    env.extend(wine_sync.get_env_vars(), EnvSource::WineSync);
+    env.extend(wine_debug.get_env_vars(), EnvSource::WineDebug);
+
+    // Apply wine locale and timezone
+    for issue in config.game.wine.locale.validate() {
+        tracing::warn!("{issue}");
+    }
+
+    if !dry_run {
+        // Wineserver overwrites the registry files on exit
+        if !crate::prefix::find_wineservers(&config.game.wine.prefix).is_empty() {
+            tracing::warn!("Wine codepages are not changed because wineserver is running for the prefix");
+        }
+
+        else if let Err(err) = config.game.wine.locale.write_registry(&config.game.wine.prefix) {
+            tracing::warn!("Failed to set wine codepages: {err}");
+        }
+    }
+
+    env.extend(config.game.wine.locale.get_env_vars(config.game.wine.language), EnvSource::WineLang);
+
+    if runner.has_files() {
+        env.extend(config.game.wine.shared_libraries.get_env_vars(wine_folder), EnvSource::SharedLibraries);
+    }
 
-    command.envs(&config.game.environment);
+    // Select wine graphics driver
+    let display_backend = config.game.wine.display_backend;
+
+    let warnings = display_backend.get_warnings(
+        &config.game.wine.virtual_desktop,
+        &config.game.enhancements.fsr,
+        &config.game.enhancements.gamescope
+    );
+
+    for warning in warnings {
+        tracing::warn!("{warning}");
+    }
+
+    if !dry_run {
+        // Wineserver overwrites the registry files on exit
+        if !crate::prefix::find_wineservers(&config.game.wine.prefix).is_empty() {
+            tracing::warn!("Wine graphics driver is not changed because wineserver is running for the prefix");
+        }
+
+        else if let Err(err) = display_backend.write_registry(&config.game.wine.prefix) {
+            tracing::warn!("Failed to set wine graphics driver: {err}");
+        }
+    }
+
+    env.extend(display_backend.get_env_vars(), EnvSource::DisplayBackend);
+
+    // Config and user environment overrides take precedence over the components ones
+    dll_overrides.merge(&config.game.wine.dll_overrides);
+
+    if let Some(value) = config.game.environment.get("WINEDLLOVERRIDES") {
+        dll_overrides.merge_env(value);
+    }
+
+    let mut persist_overrides = config.game.wine.dll_overrides.persist;
+
+    if !dry_run {
+        // Wineserver overwrites the registry files on exit
+        let wineserver_running = !crate::prefix::find_wineservers(&config.game.wine.prefix).is_empty();
+
+        if persist_overrides {
+            let result = if wineserver_running {
+                Err(anyhow::anyhow!("wineserver is running for the prefix"))
+            } else {
+                dll_overrides.write_registry(&config.game.wine.prefix)
+            };
+
+            if let Err(err) = result {
+                tracing::warn!("Failed to persist dll overrides, using WINEDLLOVERRIDES instead: {err}");
+
+                persist_overrides = false;
+            }
+        }
+
+        else if !wineserver_running {
+            if let Err(err) = DllOverrides::remove_registry(&config.game.wine.prefix) {
+                tracing::warn!("Failed to remove persisted dll overrides: {err}");
+            }
+        }
+    }
+
+    if persist_overrides {
+        env.unset("WINEDLLOVERRIDES", EnvSource::DllOverrides);
+    }
+
+    else {
+        env.extend(dll_overrides.get_env_vars(), EnvSource::DllOverrides);
+    }
+
+    // User environment variables take precedence over all the other sources.
+    // WINEDLLOVERRIDES value is already merged into the dll overrides
+    env.user(config.game.environment.iter().filter(|(key, _)| key.as_str() != "WINEDLLOVERRIDES"));
+
+    for conflict in env.conflicts() {
+        tracing::debug!("Environment variable overwritten: {conflict}");
+    }
+
+    if dry_run {
+        return Ok(env);
+    }
+
+    env.apply(&mut command);
 
     #[cfg(feature = "sessions")]
     if let Some(current) = Sessions::get_current()? {
@@ -227,6 +629,13 @@ pub fn run() -> anyhow::Result<()> {
 
     tracing::info!("Running the game with command: {variables} bash -c \"{bash_command}\"");
 
+    // Create the wine trace file before the game is spawned
+    // so a failure here doesn't leave it running without readers
+    let mut trace_output = match &wine_debug.trace_file {
+        Some(path) => Some(File::create(path)?),
+        None => None
+    };
+
     // We use real current dir here because sandboxed one
     // obviously doesn't exist
     let mut child = command.current_dir(config.game.path.for_edition(config.launcher.edition))
@@ -278,7 +687,7 @@ pub fn run() -> anyhow::Result<()> {
         }));
     }
 
-    // Redirect stderr to the game.log file
+    // Redirect stderr to the game.log or wine trace file
     if let Some(mut stderr) = child.stderr.take() {
         let game_output = game_output.clone();
         let written = written.clone();
@@ -291,6 +700,13 @@ pub fn run() -> anyhow::Result<()> {
                     break;
                 }
 
+                // Wine debug output is written to the trace file without size limit
+                if let Some(trace_output) = &mut trace_output {
+                    trace_output.write_all(&buf[..read])?;
+
+                    continue;
+                }
+
                 let Ok(mut game_output) = game_output.lock() else {
                     break;
                 };
@@ -341,10 +757,21 @@ pub fn run() -> anyhow::Result<()> {
         }
     }
 
+    if let Some(mut record) = launch_record {
+        match record.finish() {
+            Ok(()) => match &record.summary {
+                Some(summary) => tracing::info!("Frametimes: {summary}"),
+                None => tracing::warn!("No frames were logged by MangoHud")
+            }
+
+            Err(err) => tracing::warn!("Failed to calculate frametimes summary: {err}")
+        }
+    }
+
     #[cfg(feature = "sessions")]
     if let Some(current) = Sessions::get_current()? {
         Sessions::update(current, &config.game.wine.prefix)?;
     }
 
-    Ok(())
+    Ok(env)
 }
@@ -4,6 +4,8 @@ use anime_game_core::prelude::*;
 use anime_game_core::star_rail::prelude::*;
 
 use crate::config::ConfigExt;
+use crate::registry::{Registry, RegistryValue};
+use crate::config::schema_blanks::prelude::WineRunner;
 use crate::star_rail::config::Config;
 
 #[derive(Debug, Clone)]
@@ -83,23 +85,13 @@ impl LauncherState {
 
         // Check dxvk installation
  
-        let reg_path = params.wine_prefix.join("user.reg");
+        let registry = Registry::from_file(params.wine_prefix.join("user.reg"))?;
 
-        let reg_content = std::fs::read_to_string(&reg_path)?;
+        let dxgi = registry.get_value("Software\\Wine\\DllOverrides", "dxgi")
+            .and_then(RegistryValue::as_str)
+            .map(|value| value.split(',').next() == Some("native"));
 
-        let mut found_dxgi = false;
-
-        for line in reg_content.lines() {
-            if line.trim_start().starts_with("\"dxgi\"") {
-                found_dxgi = true;
-                
-                if !line.contains("\"native\"") {
-                    return Ok(Self::DxvkNotInstalled);
-                }
-            }
-        }
-
-        if !found_dxgi {
+        if dxgi != Some(true) {
             return Ok(Self::DxvkNotInstalled);
         }
 
@@ -213,9 +205,10 @@ impl LauncherState {
 
         match &config.game.wine.selected {
             #[cfg(feature = "components")]
-            Some(selected) if !config.game.wine.builds.join(selected).exists() => return Ok(Self::WineNotInstalled),
+            Some(selected) if !config.game.wine.build_folder(selected).exists() => return Ok(Self::WineNotInstalled),
 
-            None => return Ok(Self::WineNotInstalled),
+            // Umu can download proton build itself
+            None if config.game.wine.runner != WineRunner::Umu => return Ok(Self::WineNotInstalled),
 
             _ => ()
         }
@@ -6,10 +6,19 @@ use crate::config::schema_blanks::sandbox::Sandbox;
 
 #[cfg(feature = "components")]
 use crate::components::{
+    advisor::{self, UpdateAdvice},
+    custom,
+    gc::ComponentsUsage,
+    proton,
+    runner::Runner,
     wine::Version as WineVersion,
-    dxvk::Version as DxvkVersion
+    dxvk::Version as DxvkVersion,
+    vkd3d::Version as Vkd3dVersion
 };
 
+#[cfg(feature = "components")]
+use crate::config::schema_blanks::prelude::WineRunner;
+
 pub mod launcher;
 pub mod game;
 
@@ -72,10 +81,43 @@ impl From<&JsonValue> for Schema {
 impl Schema {
     #[cfg(feature = "components")]
     /// Get selected wine version
+    ///
+    /// Custom runners from the manifest take precedence over the components
+    /// index, and wine builds detected in the builds folder are used last
     pub fn get_selected_wine(&self) -> anyhow::Result<Option<WineVersion>> {
-        match &self.game.wine.selected {
-            Some(selected) => WineVersion::find_in(&self.components.path, selected),
-            None => Ok(None)
+        let Some(selected) = &self.game.wine.selected else {
+            return Ok(None);
+        };
+
+        let manifest = custom::get_manifest(&self.game.wine.custom_runners)?;
+
+        if let Some(runner) = manifest.into_iter().find(|runner| &runner.name == selected) {
+            return Ok(Some(runner.to_version()));
+        }
+
+        if let Some(version) = WineVersion::find_in(&self.components.path, selected)? {
+            return Ok(Some(version));
+        }
+
+        Ok(custom::find_in(&self.components.path, &self.game.wine.custom_runners, &self.game.wine.builds, selected)?
+            .map(|runner| runner.to_version()))
+    }
+
+    #[cfg(feature = "components")]
+    /// Get selected runner
+    ///
+    /// Depending on the `game.wine.runner` field this is either the selected
+    /// wine version, installed proton build, or umu with optional proton build
+    pub fn get_selected_runner(&self) -> anyhow::Result<Option<Runner>> {
+        match self.game.wine.runner {
+            WineRunner::Wine => Ok(self.get_selected_wine()?.map(Runner::Wine)),
+
+            WineRunner::Proton => Ok(self.game.wine.selected.as_ref()
+                .and_then(proton::find)
+                .map(Runner::Proton)),
+
+            WineRunner::Umu => Ok(Some(Runner::Umu(self.game.wine.selected.as_ref()
+                .and_then(proton::find))))
         }
     }
 
@@ -87,4 +129,57 @@ impl Schema {
             None => Ok(None)
         }
     }
+
+    #[cfg(feature = "components")]
+    /// Get selected vkd3d version
+    pub fn get_selected_vkd3d(&self) -> anyhow::Result<Option<Vkd3dVersion>> {
+        match crate::components::vkd3d::get_version(&self.game.wine.prefix)? {
+            Some(version) => Vkd3dVersion::find_in(&self.components.path, version),
+            None => Ok(None)
+        }
+    }
+
+    #[cfg(feature = "components")]
+    /// Get components used by this config
+    pub fn get_components_usage(&self) -> ComponentsUsage {
+        ComponentsUsage {
+            components: self.components.path.clone(),
+            wine_builds: self.game.wine.builds.clone(),
+            dxvk_builds: self.game.dxvk.builds.clone(),
+            vkd3d_builds: self.game.vkd3d.builds.clone(),
+            selected_wine: self.game.wine.selected.clone(),
+            prefix: self.game.wine.prefix.clone()
+        }
+    }
+
+    #[cfg(feature = "components")]
+    /// Check if selected wine and installed dxvk versions should be updated
+    ///
+    /// Custom runners and non-wine runners are not checked. Empty list means
+    /// that everything is up to date
+    pub fn get_update_advice(&self) -> anyhow::Result<Vec<UpdateAdvice>> {
+        let mut advice = Vec::new();
+
+        if let (WineRunner::Wine, Some(selected)) = (self.game.wine.runner, &self.game.wine.selected) {
+            let is_custom = match self.game.wine.get_custom_runners(&self.components.path) {
+                Ok(runners) => runners.iter().any(|runner| &runner.name == selected),
+
+                Err(err) => {
+                    tracing::warn!("Failed to read custom runners: {err}");
+
+                    false
+                }
+            };
+
+            if !is_custom {
+                advice.extend(advisor::advise_wine(&self.components.path, selected)?);
+            }
+        }
+
+        if let Some(version) = wincompatlib::dxvk::Dxvk::get_version(&self.game.wine.prefix)? {
+            advice.extend(advisor::advise_dxvk(&self.components.path, version)?);
+        }
+
+        Ok(advice)
+    }
 }
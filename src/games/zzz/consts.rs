@@ -2,6 +2,11 @@ use std::path::PathBuf;
 
 pub const FOLDER_NAME: &str = "sleepy-launcher";
 
+/// Game id in the umu database
+///
+/// Used as the `GAMEID` variable when the game is run through `umu-run`
+pub const UMU_GAME_ID: &str = "umu-zenlesszonezero";
+
 lazy_static::lazy_static! {
     // Limit max amount of log data in a file
     // This is needed to stop wine from flushing
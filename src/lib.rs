@@ -26,6 +26,18 @@ pub mod components;
 #[cfg(feature = "sessions")]
 pub mod sessions;
 
+pub mod registry;
+pub mod env;
+
+#[cfg(feature = "components")]
+pub mod prefix;
+
+#[cfg(feature = "components")]
+pub mod shader_cache;
+
+#[cfg(feature = "config")]
+pub mod frametimes;
+
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // TODO: rewrite it to find this binary in PATH instead
@@ -0,0 +1,367 @@
+//! Wine prefix lifecycle management
+//!
+//! Creation, health check, repair and reset of the wine prefix
+//! used by the selected runner
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use wincompatlib::prelude::*;
+
+use crate::registry::{Registry, RegistryValue};
+
+pub mod snapshots;
+pub mod tweaks;
+
+pub use snapshots::{snapshot, restore};
+
+/// DXVK libraries which must have `native` dll overrides
+const DXVK_LIBRARIES: &[&str] = &["d3d9", "d3d10core", "d3d11", "dxgi"];
+
+const DLL_OVERRIDES_KEY: &str = "Software\\Wine\\DllOverrides";
+
+/// Required `dosdevices` links and their targets
+const DOS_DEVICES: &[(&str, &str)] = &[
+    ("c:", "../drive_c"),
+    ("z:", "/")
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefixIssue {
+    /// Prefix folder or its `drive_c` doesn't exist
+    NotExists,
+
+    /// `dosdevices` link is missing or broken
+    MissingDosDevice(String),
+
+    /// Prefix was created with another architecture than the selected runner has
+    ArchMismatch {
+        prefix: WineArch,
+        runner: WineArch
+    },
+
+    /// DXVK libraries are installed but their dll overrides are not set to `native`
+    DxvkOverridesMissing(Vec<String>),
+
+    /// Wineserver of another wine build is running for this prefix
+    StaleWineserver {
+        pid: u32,
+        binary: PathBuf
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefixUpdate {
+    StoppingWineserver,
+    BackingUpRegistry,
+    RemovingPrefix,
+    CreatingPrefix,
+    RestoringRegistry,
+    Repairing(PrefixIssue),
+    Finished
+}
+
+/// Get path to the wineserver binary of given wine
+fn wineserver(wine: &Wine) -> PathBuf {
+    wine.wineserver.clone().unwrap_or_else(|| {
+        wine.binary.parent()
+            .map(|folder| folder.join("wineserver"))
+            .unwrap_or_else(|| PathBuf::from("wineserver"))
+    })
+}
+
+/// Run wineserver of given wine with specified flag (`-k` to kill, `-w` to wait)
+fn run_wineserver(binary: impl AsRef<Path>, prefix: impl AsRef<Path>, flag: &str) -> anyhow::Result<()> {
+    Command::new(binary.as_ref())
+        .arg(flag)
+        .env("WINEPREFIX", prefix.as_ref())
+        .spawn()?
+        .wait()?;
+
+    Ok(())
+}
+
+/// Get architecture of the existing wine prefix from its `system.reg` file
+pub fn get_arch(prefix: impl AsRef<Path>) -> anyhow::Result<Option<WineArch>> {
+    let path = prefix.as_ref().join("system.reg");
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let registry = Registry::from_file(path)?;
+
+    Ok(registry.header.iter()
+        .find_map(|line| line.strip_prefix("#arch="))
+        .and_then(|arch| WineArch::from_str(arch.trim())))
+}
+
+/// Find wineservers running for given prefix
+///
+/// Returns list of their pids and binaries paths
+pub fn find_wineservers(prefix: impl AsRef<Path>) -> Vec<(u32, PathBuf)> {
+    let prefix = prefix.as_ref();
+    let prefix = prefix.canonicalize().unwrap_or_else(|_| prefix.to_path_buf());
+
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    let mut servers = Vec::new();
+
+    for entry in entries.flatten() {
+        let Ok(pid) = entry.file_name().to_string_lossy().parse::<u32>() else {
+            continue;
+        };
+
+        let path = entry.path();
+
+        let Ok(comm) = std::fs::read_to_string(path.join("comm")) else {
+            continue;
+        };
+
+        if comm.trim() != "wineserver" {
+            continue;
+        }
+
+        let Ok(environ) = std::fs::read(path.join("environ")) else {
+            continue;
+        };
+
+        let server_prefix = environ.split(|byte| *byte == 0)
+            .filter_map(|var| std::str::from_utf8(var).ok())
+            .find_map(|var| var.strip_prefix("WINEPREFIX="))
+            .map(PathBuf::from);
+
+        let Some(server_prefix) = server_prefix else {
+            continue;
+        };
+
+        let server_prefix = server_prefix.canonicalize().unwrap_or(server_prefix);
+
+        if server_prefix == prefix {
+            if let Ok(binary) = std::fs::read_link(path.join("exe")) {
+                servers.push((pid, binary));
+            }
+        }
+    }
+
+    servers
+}
+
+/// Create wine prefix using given wine
+///
+/// Use `wine::Version::to_wine` to get the wine of the selected runner
+#[tracing::instrument(level = "debug", skip(updater), ret)]
+pub fn init(wine: &Wine, updater: impl Fn(PrefixUpdate)) -> anyhow::Result<()> {
+    tracing::debug!("Creating wine prefix");
+
+    (updater)(PrefixUpdate::CreatingPrefix);
+
+    create(wine)?;
+
+    (updater)(PrefixUpdate::Finished);
+
+    Ok(())
+}
+
+fn create(wine: &Wine) -> anyhow::Result<()> {
+    std::fs::create_dir_all(&wine.prefix)?;
+
+    let output = wine.run_args(["wineboot", "-i"])?.wait_with_output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("Failed to create wine prefix: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    // Wine writes registry files only when wineserver is stopped
+    run_wineserver(wineserver(wine), &wine.prefix, "-w")?;
+
+    if !wine.prefix.join("drive_c").exists() {
+        anyhow::bail!("Failed to create wine prefix: drive_c folder wasn't created");
+    }
+
+    Ok(())
+}
+
+/// Check health of the wine prefix
+///
+/// Wine's arch is compared with the prefix's one, so it should
+/// come from the runner's `Features::arch` (`wine::Version::to_wine` does it)
+pub fn check(wine: &Wine) -> anyhow::Result<Vec<PrefixIssue>> {
+    let prefix = &wine.prefix;
+
+    if !prefix.join("drive_c").exists() {
+        return Ok(vec![PrefixIssue::NotExists]);
+    }
+
+    let mut issues = Vec::new();
+
+    for (device, _) in DOS_DEVICES {
+        // exists() follows the link so broken links are reported too
+        if !prefix.join("dosdevices").join(device).exists() {
+            issues.push(PrefixIssue::MissingDosDevice(device.to_string()));
+        }
+    }
+
+    if let Some(arch) = get_arch(prefix)? {
+        if arch != wine.arch {
+            issues.push(PrefixIssue::ArchMismatch {
+                prefix: arch,
+                runner: wine.arch
+            });
+        }
+    }
+
+    if let Ok(Some(_)) = wincompatlib::dxvk::Dxvk::get_version(prefix) {
+        let registry = Registry::from_file(prefix.join("user.reg"))?;
+
+        let missing = DXVK_LIBRARIES.iter()
+            .filter(|library| {
+                let value = registry.get_value(DLL_OVERRIDES_KEY, **library)
+                    .and_then(RegistryValue::as_str);
+
+                !matches!(value, Some(value) if value.split(',').next() == Some("native"))
+            })
+            .map(|library| library.to_string())
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() {
+            issues.push(PrefixIssue::DxvkOverridesMissing(missing));
+        }
+    }
+
+    let binary = wineserver(wine);
+    let binary = binary.canonicalize().unwrap_or(binary);
+
+    for (pid, server) in find_wineservers(prefix) {
+        if server != binary {
+            issues.push(PrefixIssue::StaleWineserver {
+                pid,
+                binary: server
+            });
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Repair given prefix issues
+///
+/// Architecture mismatch and missing prefix can't be repaired in place,
+/// so the prefix is recreated using the `reset` function
+#[tracing::instrument(level = "debug", skip(updater), ret)]
+pub fn repair(wine: &Wine, issues: &[PrefixIssue], preserve_keys: &[&str], updater: impl Fn(PrefixUpdate)) -> anyhow::Result<()> {
+    tracing::debug!("Repairing wine prefix");
+
+    let recreate = issues.iter().any(|issue| {
+        matches!(issue, PrefixIssue::NotExists | PrefixIssue::ArchMismatch { .. })
+    });
+
+    if recreate {
+        return reset(wine, preserve_keys, updater);
+    }
+
+    for issue in issues {
+        (updater)(PrefixUpdate::Repairing(issue.clone()));
+
+        match issue {
+            PrefixIssue::MissingDosDevice(device) => {
+                let dosdevices = wine.prefix.join("dosdevices");
+                let link = dosdevices.join(device);
+
+                let Some((_, target)) = DOS_DEVICES.iter().find(|(name, _)| *name == device.as_str()) else {
+                    continue;
+                };
+
+                if link.is_symlink() {
+                    std::fs::remove_file(&link)?;
+                }
+
+                std::fs::create_dir_all(dosdevices)?;
+                std::os::unix::fs::symlink(target, link)?;
+            }
+
+            PrefixIssue::DxvkOverridesMissing(libraries) => {
+                for library in libraries {
+                    let output = wine.run_args(["reg", "add", "HKEY_CURRENT_USER\\Software\\Wine\\DllOverrides", "/v", library, "/d", "native", "/f"])?
+                        .wait_with_output()?;
+
+                    if !output.status.success() {
+                        anyhow::bail!("Failed to set {library} dll override: {}", String::from_utf8_lossy(&output.stderr));
+                    }
+                }
+            }
+
+            PrefixIssue::StaleWineserver { binary, .. } => {
+                run_wineserver(binary, &wine.prefix, "-k")?;
+            }
+
+            PrefixIssue::NotExists |
+            PrefixIssue::ArchMismatch { .. } => unreachable!()
+        }
+    }
+
+    (updater)(PrefixUpdate::Finished);
+
+    Ok(())
+}
+
+/// Remove wine prefix and create it again
+///
+/// Registry keys from the `preserve_keys` list (e.g. game sessions
+/// from the `sessions` modules) are copied to the new prefix
+#[tracing::instrument(level = "debug", skip(updater), ret)]
+pub fn reset(wine: &Wine, preserve_keys: &[&str], updater: impl Fn(PrefixUpdate)) -> anyhow::Result<()> {
+    tracing::debug!("Resetting wine prefix");
+
+    let prefix = &wine.prefix;
+
+    (updater)(PrefixUpdate::StoppingWineserver);
+
+    for (_, server) in find_wineservers(prefix) {
+        run_wineserver(server, prefix, "-k")?;
+    }
+
+    (updater)(PrefixUpdate::BackingUpRegistry);
+
+    let user_reg = prefix.join("user.reg");
+
+    let mut preserved = Vec::new();
+
+    if user_reg.exists() {
+        let registry = Registry::from_file(&user_reg)?;
+
+        for key in preserve_keys {
+            if let Some(key) = registry.get_key(key) {
+                preserved.push(key.clone());
+            }
+        }
+    }
+
+    if prefix.exists() {
+        (updater)(PrefixUpdate::RemovingPrefix);
+
+        std::fs::remove_dir_all(prefix)?;
+    }
+
+    (updater)(PrefixUpdate::CreatingPrefix);
+
+    create(wine)?;
+
+    if !preserved.is_empty() {
+        (updater)(PrefixUpdate::RestoringRegistry);
+
+        let mut registry = Registry::from_file(&user_reg)?;
+
+        for key in preserved {
+            registry.insert_key(key);
+        }
+
+        registry.write(user_reg)?;
+    }
+
+    (updater)(PrefixUpdate::Finished);
+
+    Ok(())
+}
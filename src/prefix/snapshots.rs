@@ -0,0 +1,385 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+use super::DOS_DEVICES;
+
+/// File in the wine prefix which stores name of the last runner used with it
+const LAST_RUNNER_FILE: &str = ".last_runner";
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Amount of automatic snapshots to keep
+const AUTO_SNAPSHOTS_LIMIT: usize = 3;
+
+/// Amount of files copied by a single `cp` call
+const COPY_BATCH_SIZE: usize = 512;
+
+/// Folders which are not stored in snapshots
+///
+/// Paths are relative to the prefix and compared in lowercase,
+/// `*` matches any single folder name. These are temp files
+/// and shader caches which can take gigabytes and are regenerated
+/// by the game or drivers
+const SKIPPED_FOLDERS: &[&str] = &[
+    "drive_c/windows/temp",
+    "drive_c/users/*/temp",
+    "drive_c/users/*/appdata/local/temp",
+    "drive_c/users/*/appdata/local/d3dscache",
+    "drive_c/users/*/appdata/local/nvidia/dxcache",
+    "drive_c/users/*/appdata/local/nvidia/glcache",
+    "drive_c/users/*/appdata/local/amd/dxcache",
+    "drive_c/users/*/appdata/local/amd/dxccache",
+    "drive_c/users/*/appdata/local/amd/glcache",
+    "drive_c/programdata/nvidia corporation/nv_cache"
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+
+    /// Snapshot creation time in seconds since unix epoch
+    pub created_at: u64,
+
+    /// Name of the runner the prefix was used with
+    pub runner: Option<String>,
+
+    /// DXVK version installed in the prefix
+    pub dxvk: Option<String>,
+
+    /// Vkd3d-proton version installed in the prefix
+    pub vkd3d: Option<String>,
+
+    /// Snapshot was taken automatically before a runner change
+    pub automatic: bool
+}
+
+#[inline]
+/// Get folder which stores snapshots of given wine prefix
+pub fn snapshots_folder(prefix: impl AsRef<Path>) -> PathBuf {
+    prefix.as_ref().with_extension("snapshots")
+}
+
+/// Get name of the last runner used with given prefix
+pub fn get_last_runner(prefix: impl AsRef<Path>) -> Option<String> {
+    std::fs::read_to_string(prefix.as_ref().join(LAST_RUNNER_FILE))
+        .map(|runner| runner.trim().to_string())
+        .ok()
+}
+
+/// List snapshots of given prefix sorted from the newest to the oldest
+pub fn list(prefix: impl AsRef<Path>) -> anyhow::Result<Vec<Snapshot>> {
+    let folder = snapshots_folder(prefix);
+
+    if !folder.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+
+    for entry in std::fs::read_dir(folder)?.flatten() {
+        let manifest = entry.path().join(MANIFEST_FILE);
+
+        if !manifest.exists() {
+            continue;
+        }
+
+        match serde_json::from_slice::<Snapshot>(&std::fs::read(&manifest)?) {
+            Ok(snapshot) => snapshots.push(snapshot),
+            Err(err) => tracing::warn!("Failed to read snapshot manifest {manifest:?}: {err}")
+        }
+    }
+
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    Ok(snapshots)
+}
+
+/// Take snapshot of the wine prefix with given name
+///
+/// Files are copied using reflinks if the filesystem supports them.
+/// `dosdevices` links, temp files and shader caches are not stored
+#[tracing::instrument(level = "debug", ret)]
+pub fn snapshot(prefix: impl AsRef<Path> + std::fmt::Debug, name: impl AsRef<str> + std::fmt::Debug) -> anyhow::Result<Snapshot> {
+    tracing::debug!("Taking prefix snapshot");
+
+    take(prefix.as_ref(), name.as_ref(), false)
+}
+
+fn take(prefix: &Path, name: &str, automatic: bool) -> anyhow::Result<Snapshot> {
+    if name.is_empty() || name.starts_with('.') || name.contains('/') {
+        anyhow::bail!("Wrong snapshot name: {name}");
+    }
+
+    if !prefix.join("drive_c").exists() {
+        anyhow::bail!("Wine prefix doesn't exist: {prefix:?}");
+    }
+
+    let folder = snapshots_folder(prefix).join(name);
+
+    if folder.exists() {
+        anyhow::bail!("Snapshot {name} already exists");
+    }
+
+    let snapshot = Snapshot {
+        name: name.to_string(),
+
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)?
+            .as_secs(),
+
+        runner: get_last_runner(prefix),
+        dxvk: wincompatlib::dxvk::Dxvk::get_version(prefix).ok().flatten(),
+        vkd3d: crate::components::vkd3d::get_version(prefix).ok().flatten(),
+        automatic
+    };
+
+    if let Err(err) = copy_prefix(prefix, &folder.join("prefix")) {
+        if folder.exists() {
+            std::fs::remove_dir_all(&folder)?;
+        }
+
+        return Err(err);
+    }
+
+    std::fs::write(folder.join(MANIFEST_FILE), serde_json::to_string_pretty(&snapshot)?)?;
+
+    Ok(snapshot)
+}
+
+/// Restore wine prefix from the snapshot with given name
+///
+/// Current prefix is kept until the snapshot is copied
+/// so it's not lost if restoration fails
+#[tracing::instrument(level = "debug", ret)]
+pub fn restore(prefix: impl AsRef<Path> + std::fmt::Debug, name: impl AsRef<str> + std::fmt::Debug) -> anyhow::Result<Snapshot> {
+    tracing::debug!("Restoring prefix snapshot");
+
+    let prefix = prefix.as_ref();
+    let name = name.as_ref();
+
+    let Some(snapshot) = list(prefix)?.into_iter().find(|snapshot| snapshot.name == name) else {
+        anyhow::bail!("Snapshot {name} doesn't exist");
+    };
+
+    for (_, server) in super::find_wineservers(prefix) {
+        super::run_wineserver(server, prefix, "-k")?;
+    }
+
+    let backup = prefix.with_extension("restoring");
+
+    if backup.exists() {
+        std::fs::remove_dir_all(&backup)?;
+    }
+
+    if prefix.exists() {
+        std::fs::rename(prefix, &backup)?;
+    }
+
+    if let Err(err) = copy_prefix(&snapshots_folder(prefix).join(name).join("prefix"), prefix) {
+        if prefix.exists() {
+            std::fs::remove_dir_all(prefix)?;
+        }
+
+        if backup.exists() {
+            std::fs::rename(&backup, prefix)?;
+        }
+
+        return Err(err);
+    }
+
+    let dosdevices = prefix.join("dosdevices");
+
+    std::fs::create_dir_all(&dosdevices)?;
+
+    for (device, target) in DOS_DEVICES {
+        std::os::unix::fs::symlink(target, dosdevices.join(device))?;
+    }
+
+    if backup.exists() {
+        std::fs::remove_dir_all(backup)?;
+    }
+
+    Ok(snapshot)
+}
+
+/// Remove snapshot with given name
+pub fn remove(prefix: impl AsRef<Path>, name: impl AsRef<str>) -> anyhow::Result<()> {
+    let name = name.as_ref();
+
+    if name.is_empty() || name.starts_with('.') || name.contains('/') {
+        anyhow::bail!("Wrong snapshot name: {name}");
+    }
+
+    let folder = snapshots_folder(prefix).join(name);
+
+    if folder.exists() {
+        std::fs::remove_dir_all(folder)?;
+    }
+
+    Ok(())
+}
+
+/// Remove all the snapshots except of the `keep` newest ones
+///
+/// If `automatic_only` is set, then only automatic snapshots
+/// are counted and removed
+///
+/// Returns list of removed snapshots
+pub fn prune(prefix: impl AsRef<Path>, keep: usize, automatic_only: bool) -> anyhow::Result<Vec<Snapshot>> {
+    let prefix = prefix.as_ref();
+
+    let removed = list(prefix)?
+        .into_iter()
+        .filter(|snapshot| !automatic_only || snapshot.automatic)
+        .skip(keep)
+        .collect::<Vec<_>>();
+
+    for snapshot in &removed {
+        remove(prefix, &snapshot.name)?;
+    }
+
+    Ok(removed)
+}
+
+/// Remember runner used with the prefix
+///
+/// If `auto_snapshot` is set and the runner was changed since the last
+/// launch, then a snapshot of the prefix is taken before the change.
+/// Snapshot failure is logged as a warning and doesn't stop the runner tracking
+pub fn track_runner(prefix: impl AsRef<Path>, runner: impl AsRef<str>, auto_snapshot: bool) -> anyhow::Result<Option<Snapshot>> {
+    let prefix = prefix.as_ref();
+    let runner = runner.as_ref();
+
+    if !prefix.join("drive_c").exists() {
+        return Ok(None);
+    }
+
+    let mut snapshot = None;
+
+    if let Some(last_runner) = get_last_runner(prefix) {
+        if auto_snapshot && last_runner != runner {
+            let created_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)?
+                .as_secs();
+
+            // Failed snapshot shouldn't prevent the runner from being remembered,
+            // otherwise it would be retried on every launch
+            match take(prefix, &format!("auto-{created_at}"), true) {
+                Ok(taken) => {
+                    snapshot = Some(taken);
+
+                    if let Err(err) = prune(prefix, AUTO_SNAPSHOTS_LIMIT, true) {
+                        tracing::warn!("Failed to prune automatic prefix snapshots: {err}");
+                    }
+                }
+
+                Err(err) => tracing::warn!("Failed to take prefix snapshot before the runner change: {err}")
+            }
+        }
+    }
+
+    std::fs::write(prefix.join(LAST_RUNNER_FILE), runner)?;
+
+    Ok(snapshot)
+}
+
+/// Copy wine prefix to another folder skipping `dosdevices` and caches
+fn copy_prefix(from: &Path, to: &Path) -> anyhow::Result<()> {
+    let mut folders = Vec::new();
+    let mut files = Vec::new();
+
+    collect_files(from, Path::new(""), &mut folders, &mut files)?;
+
+    std::fs::create_dir_all(to)?;
+
+    for folder in folders {
+        std::fs::create_dir_all(to.join(folder))?;
+    }
+
+    for batch in files.chunks(COPY_BATCH_SIZE) {
+        // GNU cp makes reflinks if they're supported by the filesystem
+        // and falls back to the usual copy otherwise
+        let copied = Command::new("cp")
+            .args(["--no-dereference", "--preserve=mode,timestamps", "--reflink=auto", "--parents", "-t"])
+            .arg(to)
+            .arg("--")
+            .args(batch)
+            .current_dir(from)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false);
+
+        if !copied {
+            for file in batch {
+                copy_file(&from.join(file), &to.join(file))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect relative paths of the folders and files which should be copied
+fn collect_files(root: &Path, relative: &Path, folders: &mut Vec<PathBuf>, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(root.join(relative))?.flatten() {
+        let name = entry.file_name();
+        let path = relative.join(&name);
+
+        let metadata = entry.path().symlink_metadata()?;
+
+        if metadata.is_dir() {
+            let name = name.to_string_lossy().to_ascii_lowercase();
+
+            if (relative.as_os_str().is_empty() && name == "dosdevices") || is_skipped(&path) {
+                continue;
+            }
+
+            folders.push(path.clone());
+
+            collect_files(root, &path, folders, files)?;
+        }
+
+        else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if folder with given path relative to the prefix should not be stored in snapshots
+fn is_skipped(relative: &Path) -> bool {
+    let components = relative.components()
+        .map(|component| component.as_os_str().to_string_lossy().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+
+    SKIPPED_FOLDERS.iter().any(|pattern| {
+        let pattern = pattern.split('/').collect::<Vec<_>>();
+
+        pattern.len() == components.len() && pattern.iter()
+            .zip(&components)
+            .all(|(pattern, component)| *pattern == "*" || pattern == component)
+    })
+}
+
+/// Copy file without following symlinks
+fn copy_file(from: &Path, to: &Path) -> anyhow::Result<()> {
+    if to.is_symlink() || to.exists() {
+        std::fs::remove_file(to)?;
+    }
+
+    if from.is_symlink() {
+        std::os::unix::fs::symlink(std::fs::read_link(from)?, to)?;
+    }
+
+    else {
+        std::fs::copy(from, to)?;
+    }
+
+    Ok(())
+}
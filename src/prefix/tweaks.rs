@@ -0,0 +1,399 @@
+use std::path::{Path, PathBuf, Component};
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use wincompatlib::prelude::*;
+
+/// File in the wine prefix which stores applied tweaks
+const APPLIED_TWEAKS_FILE: &str = ".tweaks.json";
+
+const DLL_OVERRIDES_KEY: &str = "HKEY_CURRENT_USER\\Software\\Wine\\DllOverrides";
+
+const FONT_REPLACEMENTS_KEY: &str = "HKEY_CURRENT_USER\\Software\\Wine\\Fonts\\Replacements";
+
+/// Fonts files extensions
+const FONT_EXTENSIONS: &[&str] = &["ttf", "ttc", "otf", "otc", "fon"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TweakAction {
+    /// Set registry value. If `value` is not specified, then it's removed
+    ///
+    /// Default value of the key is used if `name` is not specified
+    Registry {
+        key: String,
+        name: Option<String>,
+        value: Option<String>,
+
+        /// Value type, e.g. `REG_DWORD`. `REG_SZ` is used by default
+        kind: Option<String>
+    },
+
+    /// Set dll override mode, e.g. `native,builtin`. If `mode` is not specified,
+    /// then the override is removed
+    DllOverride {
+        library: String,
+        mode: Option<String>
+    },
+
+    /// Copy file to the prefix. `target` is relative to the prefix folder
+    File {
+        source: PathBuf,
+        target: PathBuf
+    },
+
+    /// Install fonts from the archive, folder or a single font file
+    Fonts {
+        source: PathBuf
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tweak {
+    /// Unique tweak (verb) name which is used in the `game.wine.tweaks` config field
+    pub name: String,
+
+    /// Human readable tweak name
+    pub title: String,
+
+    pub actions: Vec<TweakAction>
+}
+
+impl From<&JsonValue> for Tweak {
+    fn from(value: &JsonValue) -> Self {
+        let name = value.get("name")
+            .and_then(JsonValue::as_str)
+            .map(String::from)
+            .unwrap_or_default();
+
+        Self {
+            title: value.get("title")
+                .and_then(JsonValue::as_str)
+                .map(String::from)
+                .unwrap_or_else(|| name.clone()),
+
+            actions: match value.get("actions").and_then(JsonValue::as_array) {
+                Some(actions) => actions.iter()
+                    .filter_map(|action| {
+                        match serde_json::from_value(action.clone()) {
+                            Ok(action) => Some(action),
+
+                            Err(err) => {
+                                tracing::warn!("Skipping wrong action of the {name} tweak: {err}");
+
+                                None
+                            }
+                        }
+                    })
+                    .collect(),
+
+                None => Vec::new()
+            },
+
+            name
+        }
+    }
+}
+
+impl Tweak {
+    #[inline]
+    /// Check if current tweak is applied to the prefix
+    ///
+    /// Tweak is considered not applied if its actions were changed since the last time
+    pub fn is_applied(&self, prefix: impl AsRef<Path>) -> anyhow::Result<bool> {
+        Ok(get_applied(prefix)?.get(&self.name) == Some(&self.actions))
+    }
+
+    /// Apply current tweak to the prefix and remember it
+    #[tracing::instrument(level = "debug", skip(wine), ret)]
+    pub fn apply(&self, wine: &Wine) -> anyhow::Result<()> {
+        tracing::debug!("Applying prefix tweak");
+
+        for action in &self.actions {
+            match action {
+                TweakAction::Registry { key, name, value, kind } => {
+                    set_registry_value(wine, key, name.as_deref(), value.as_deref(), kind.as_deref())?;
+                }
+
+                TweakAction::DllOverride { library, mode } => {
+                    set_registry_value(wine, DLL_OVERRIDES_KEY, Some(library.as_str()), mode.as_deref(), None)?;
+                }
+
+                TweakAction::File { source, target } => {
+                    // Don't allow tweaks to write files outside of the prefix
+                    let escapes = target.components().any(|component| {
+                        !matches!(component, Component::Normal(_) | Component::CurDir)
+                    });
+
+                    if escapes {
+                        anyhow::bail!("Tweak {} tries to write file outside of the prefix: {target:?}", self.name);
+                    }
+
+                    let target = wine.prefix.join(target);
+
+                    if let Some(parent) = target.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+
+                    std::fs::copy(source, target)?;
+                }
+
+                TweakAction::Fonts { source } => install_fonts(wine, source)?
+            }
+        }
+
+        let mut applied = get_applied(&wine.prefix)?;
+
+        applied.insert(self.name.clone(), self.actions.clone());
+
+        Ok(std::fs::write(wine.prefix.join(APPLIED_TWEAKS_FILE), serde_json::to_string_pretty(&applied)?)?)
+    }
+}
+
+/// Set or remove registry value using `reg` utility
+fn set_registry_value(wine: &Wine, key: &str, name: Option<&str>, value: Option<&str>, kind: Option<&str>) -> anyhow::Result<()> {
+    let mut args = vec!["reg"];
+
+    match value {
+        Some(_) => args.extend(["add", key]),
+        None => args.extend(["delete", key])
+    }
+
+    match name {
+        Some(name) => args.extend(["/v", name]),
+        None => args.push("/ve")
+    }
+
+    if let Some(value) = value {
+        args.extend(["/t", kind.unwrap_or("REG_SZ"), "/d", value]);
+    }
+
+    args.push("/f");
+
+    let output = wine.run_args(args)?.wait_with_output()?;
+
+    // Deletion fails if there's no such value, so we don't check its status
+    if value.is_some() && !output.status.success() {
+        anyhow::bail!("Failed to set registry value {key}\\{}: {}", name.unwrap_or("@"), String::from_utf8_lossy(&output.stderr));
+    }
+
+    Ok(())
+}
+
+/// Copy fonts from the archive, folder or a single font file to the prefix
+fn install_fonts(wine: &Wine, source: &Path) -> anyhow::Result<()> {
+    let fonts = wine.prefix.join("drive_c/windows/Fonts");
+
+    std::fs::create_dir_all(&fonts)?;
+
+    if is_font(source) {
+        std::fs::copy(source, fonts.join(source.file_name().unwrap_or_default()))?;
+
+        return Ok(());
+    }
+
+    if source.is_dir() {
+        return copy_fonts(source, &fonts);
+    }
+
+    let temp = std::env::temp_dir().join(format!(".anime-launcher-sdk-fonts-{}", std::process::id()));
+
+    if temp.exists() {
+        std::fs::remove_dir_all(&temp)?;
+    }
+
+    std::fs::create_dir_all(&temp)?;
+
+    let result = extract(source, &temp).and_then(|_| copy_fonts(&temp, &fonts));
+
+    std::fs::remove_dir_all(&temp)?;
+
+    result
+}
+
+#[inline]
+fn is_font(path: &Path) -> bool {
+    path.is_file() && path.extension()
+        .map(|ext| FONT_EXTENSIONS.contains(&ext.to_string_lossy().to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Copy all the fonts from given folder and its subfolders
+fn copy_fonts(from: &Path, to: &Path) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(from)?.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            copy_fonts(&path, to)?;
+        }
+
+        else if is_font(&path) {
+            std::fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Extract archive using system utilities
+fn extract(archive: &Path, folder: &Path) -> anyhow::Result<()> {
+    let name = archive.to_string_lossy().to_ascii_lowercase();
+
+    let mut command = if name.ends_with(".zip") {
+        let mut command = Command::new("unzip");
+
+        command.arg("-o").arg(archive).arg("-d").arg(folder);
+
+        command
+    }
+
+    else if name.ends_with(".7z") {
+        let mut command = Command::new("7z");
+
+        command.arg("x").arg(archive).arg(format!("-o{}", folder.to_string_lossy())).arg("-y");
+
+        command
+    }
+
+    else {
+        let mut command = Command::new("tar");
+
+        command.arg("-xf").arg(archive).arg("-C").arg(folder);
+
+        command
+    };
+
+    let output = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("Failed to extract archive {archive:?}: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    Ok(())
+}
+
+/// Get tweaks applied to the prefix with their actions
+pub fn get_applied(prefix: impl AsRef<Path>) -> anyhow::Result<HashMap<String, Vec<TweakAction>>> {
+    let path = prefix.as_ref().join(APPLIED_TWEAKS_FILE);
+
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+/// Font replacement tweak for the CJK languages
+///
+/// Windows fonts used by games are replaced by the Noto CJK ones
+/// which are commonly installed in linux distributions
+fn cjk_fonts(name: &str, title: &str, font: &str) -> Tweak {
+    let replaced = [
+        "MS Gothic", "MS PGothic", "MS UI Gothic", "MS Mincho", "MS PMincho", "Meiryo", "Meiryo UI",
+        "SimSun", "NSimSun", "SimHei", "Microsoft YaHei", "Microsoft YaHei UI",
+        "Gulim", "Dotum", "Batang", "Malgun Gothic"
+    ];
+
+    Tweak {
+        name: name.to_string(),
+        title: title.to_string(),
+
+        actions: replaced.into_iter()
+            .map(|replaced| TweakAction::Registry {
+                key: FONT_REPLACEMENTS_KEY.to_string(),
+                name: Some(replaced.to_string()),
+                value: Some(font.to_string()),
+                kind: None
+            })
+            .collect()
+    }
+}
+
+/// Get tweaks which are always available
+pub fn builtin() -> Vec<Tweak> {
+    vec![
+        cjk_fonts("cjk-fonts-japanese", "Japanese fonts replacement", "Noto Sans CJK JP"),
+        cjk_fonts("cjk-fonts-chinese", "Chinese fonts replacement", "Noto Sans CJK SC"),
+        cjk_fonts("cjk-fonts-korean", "Korean fonts replacement", "Noto Sans CJK KR")
+    ]
+}
+
+/// Read tweaks manifest
+///
+/// Returns empty list if the manifest doesn't exist
+pub fn get_manifest(manifest: impl AsRef<Path>) -> anyhow::Result<Vec<Tweak>> {
+    let manifest = manifest.as_ref();
+
+    if !manifest.exists() {
+        return Ok(Vec::new());
+    }
+
+    let tweaks = serde_json::from_slice::<JsonValue>(&std::fs::read(manifest)?)?;
+
+    match tweaks.as_array() {
+        Some(tweaks) => Ok(tweaks.iter()
+            .map(Tweak::from)
+            .filter(|tweak| !tweak.name.is_empty())
+            .collect()),
+
+        None => anyhow::bail!("Wrong tweaks manifest structure: it must be a list")
+    }
+}
+
+/// List tweaks from the manifest merged with the builtin ones
+///
+/// Manifest entries take precedence over builtin ones
+pub fn get_tweaks(manifest: impl AsRef<Path>) -> anyhow::Result<Vec<Tweak>> {
+    let mut tweaks = get_manifest(manifest)?;
+
+    for tweak in builtin() {
+        if !tweaks.iter().any(|custom| custom.name == tweak.name) {
+            tweaks.push(tweak);
+        }
+    }
+
+    Ok(tweaks)
+}
+
+/// Apply given tweaks to the wine prefix if they're not applied yet
+///
+/// Waits for the prefix's wineserver to exit after every applied tweak
+/// so the registry files can be edited right after this call.
+/// Returns list of applied tweaks names
+#[tracing::instrument(level = "debug", skip(wine), ret)]
+pub fn ensure(wine: &Wine, manifest: impl AsRef<Path> + std::fmt::Debug, verbs: &[String]) -> anyhow::Result<Vec<String>> {
+    if verbs.is_empty() || !wine.prefix.join("drive_c").exists() {
+        return Ok(Vec::new());
+    }
+
+    let tweaks = get_tweaks(manifest)?;
+    let applied = get_applied(&wine.prefix)?;
+
+    let mut newly_applied = Vec::new();
+
+    for verb in verbs {
+        let Some(tweak) = tweaks.iter().find(|tweak| &tweak.name == verb) else {
+            anyhow::bail!("Unknown prefix tweak: {verb}");
+        };
+
+        if applied.get(&tweak.name) != Some(&tweak.actions) {
+            let result = tweak.apply(wine);
+
+            // Wine writes registry files only when wineserver is stopped,
+            // and the launcher edits them directly right after the tweaks
+            super::run_wineserver(super::wineserver(wine), &wine.prefix, "-w")?;
+
+            result?;
+
+            newly_applied.push(tweak.name.clone());
+        }
+    }
+
+    Ok(newly_applied)
+}
@@ -0,0 +1,664 @@
+//! Wine registry (`.reg`) files parser and writer
+//!
+//! Wine stores prefix registry in `system.reg`, `user.reg` and `userdef.reg`
+//! files using its own text format:
+//!
+//! ```text
+//! WINE REGISTRY Version 2
+//! ;; All keys relative to \\User\\S-1-5-21-0-0-0-1000
+//!
+//! #arch=win64
+//!
+//! [Software\\Wine\\DllOverrides] 1700000000
+//! #time=1da1b2c3d4e5f60
+//! "dxgi"="native"
+//! "value"=dword:00000001
+//! ```
+
+use std::path::Path;
+
+/// Registry value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryValue {
+    /// `"value"`
+    String(String),
+
+    /// `str(N):"value"` where `N` is the value type
+    /// (e.g. `2` for `REG_EXPAND_SZ`)
+    TypedString(u32, String),
+
+    /// `dword:00000001`
+    Dword(u32),
+
+    /// `hex:01,02,03`
+    Binary(Vec<u8>),
+
+    /// `hex(N):01,02,03` where `N` is the value type
+    /// (e.g. `7` for `REG_MULTI_SZ`)
+    TypedBinary(u32, Vec<u8>),
+
+    /// Unknown value format stored as is
+    Raw(String)
+}
+
+impl RegistryValue {
+    /// Get string value
+    ///
+    /// Returns `None` for non-string values
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) |
+            Self::TypedString(_, value) => Some(value),
+
+            _ => None
+        }
+    }
+
+    /// Get dword value
+    ///
+    /// Returns `None` for non-dword values
+    pub fn as_dword(&self) -> Option<u32> {
+        match self {
+            Self::Dword(value) => Some(*value),
+
+            _ => None
+        }
+    }
+
+    fn parse(value: &str) -> anyhow::Result<Self> {
+        let value = value.trim();
+
+        if let Some(string) = value.strip_prefix('"') {
+            let (string, _) = unescape(string, '"')?;
+
+            return Ok(Self::String(string));
+        }
+
+        if let Some(dword) = value.strip_prefix("dword:") {
+            return Ok(Self::Dword(u32::from_str_radix(dword.trim(), 16)?));
+        }
+
+        if let Some(bytes) = value.strip_prefix("hex:") {
+            return Ok(Self::Binary(parse_hex(bytes)?));
+        }
+
+        if let Some(typed) = value.strip_prefix("hex(") {
+            if let Some((kind, bytes)) = typed.split_once("):") {
+                return Ok(Self::TypedBinary(u32::from_str_radix(kind, 16)?, parse_hex(bytes)?));
+            }
+        }
+
+        if let Some(typed) = value.strip_prefix("str(") {
+            if let Some((kind, string)) = typed.split_once("):\"") {
+                let (string, _) = unescape(string, '"')?;
+
+                return Ok(Self::TypedString(u32::from_str_radix(kind, 16)?, string));
+            }
+        }
+
+        Ok(Self::Raw(value.to_string()))
+    }
+}
+
+impl std::fmt::Display for RegistryValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(value) => write!(f, "\"{}\"", escape(value, '"')),
+            Self::TypedString(kind, value) => write!(f, "str({kind:x}):\"{}\"", escape(value, '"')),
+            Self::Dword(value) => write!(f, "dword:{value:08x}"),
+            Self::Binary(bytes) => write!(f, "hex:{}", format_hex(bytes)),
+            Self::TypedBinary(kind, bytes) => write!(f, "hex({kind:x}):{}", format_hex(bytes)),
+            Self::Raw(value) => f.write_str(value)
+        }
+    }
+}
+
+/// Registry key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryKey {
+    /// Unescaped key path, e.g. `Software\Wine\DllOverrides`
+    pub path: String,
+
+    /// Last modification time of the key
+    pub timestamp: Option<u64>,
+
+    /// Key metadata lines, e.g. `#time=1da1b2c3d4e5f60`
+    pub metadata: Vec<String>,
+
+    /// Key values. `None` name stands for the default (`@`) value
+    pub values: Vec<(Option<String>, RegistryValue)>
+}
+
+impl RegistryKey {
+    #[inline]
+    pub fn new(path: impl ToString) -> Self {
+        Self {
+            path: path.to_string(),
+            timestamp: None,
+            metadata: Vec::new(),
+            values: Vec::new()
+        }
+    }
+
+    /// Check if key has given path
+    ///
+    /// Registry paths are case insensitive
+    #[inline]
+    pub fn is(&self, path: impl AsRef<str>) -> bool {
+        self.path.eq_ignore_ascii_case(path.as_ref())
+    }
+
+    /// Get value with given name. Use `None` to get the default value
+    pub fn get<'a>(&self, name: impl Into<Option<&'a str>>) -> Option<&RegistryValue> {
+        let name = name.into();
+
+        self.values.iter()
+            .find(|(value_name, _)| names_equal(value_name.as_deref(), name))
+            .map(|(_, value)| value)
+    }
+
+    /// Set value with given name. Use `None` to set the default value
+    pub fn set<'a>(&mut self, name: impl Into<Option<&'a str>>, value: RegistryValue) {
+        let name = name.into();
+
+        match self.values.iter_mut().find(|(value_name, _)| names_equal(value_name.as_deref(), name)) {
+            Some((_, current)) => *current = value,
+            None => self.values.push((name.map(String::from), value))
+        }
+    }
+
+    /// Remove value with given name. Use `None` to remove the default value
+    pub fn remove<'a>(&mut self, name: impl Into<Option<&'a str>>) -> Option<RegistryValue> {
+        let name = name.into();
+
+        let index = self.values.iter()
+            .position(|(value_name, _)| names_equal(value_name.as_deref(), name))?;
+
+        Some(self.values.remove(index).1)
+    }
+}
+
+impl std::fmt::Display for RegistryKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}]", escape(&self.path, ']'))?;
+
+        if let Some(timestamp) = self.timestamp {
+            write!(f, " {timestamp}")?;
+        }
+
+        writeln!(f)?;
+
+        for line in &self.metadata {
+            writeln!(f, "{line}")?;
+        }
+
+        for (name, value) in &self.values {
+            match name {
+                Some(name) => writeln!(f, "\"{}\"={value}", escape(name, '"'))?,
+                None => writeln!(f, "@={value}")?
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Wine registry file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Registry {
+    /// Lines before the first key, e.g. `WINE REGISTRY Version 2`
+    pub header: Vec<String>,
+
+    pub keys: Vec<RegistryKey>
+}
+
+impl Registry {
+    /// Parse registry file content
+    ///
+    /// Both LF and CRLF line endings are supported
+    pub fn parse(content: impl AsRef<str>) -> anyhow::Result<Self> {
+        let mut registry = Self::default();
+        let mut lines = Vec::<String>::new();
+
+        // Join continued lines (long hex values)
+        for line in content.as_ref().lines() {
+            let line = line.trim_end_matches('\r');
+
+            match lines.last_mut() {
+                Some(last) if last.ends_with('\\') && !last.starts_with('[') && !last.ends_with("\\\\") => {
+                    last.pop();
+                    last.push_str(line.trim_start());
+                }
+
+                _ => lines.push(line.to_string())
+            }
+        }
+
+        for line in lines {
+            if let Some(key) = line.strip_prefix('[') {
+                let (path, rest) = unescape(key, ']')?;
+
+                let mut key = RegistryKey::new(path);
+
+                key.timestamp = rest.trim().parse().ok();
+
+                registry.keys.push(key);
+
+                continue;
+            }
+
+            let Some(key) = registry.keys.last_mut() else {
+                registry.header.push(line);
+
+                continue;
+            };
+
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() || trimmed.starts_with(';') {
+                continue;
+            }
+
+            if trimmed.starts_with('#') {
+                key.metadata.push(trimmed.to_string());
+
+                continue;
+            }
+
+            let (name, value) = if let Some(value) = trimmed.strip_prefix("@=") {
+                (None, value)
+            }
+
+            else if let Some(name) = trimmed.strip_prefix('"') {
+                let (name, rest) = unescape(name, '"')?;
+
+                let Some(value) = rest.trim_start().strip_prefix('=') else {
+                    anyhow::bail!("Wrong registry value format: {line}");
+                };
+
+                (Some(name), value)
+            }
+
+            else {
+                anyhow::bail!("Wrong registry value format: {line}");
+            };
+
+            key.values.push((name, RegistryValue::parse(value)?));
+        }
+
+        // Remove trailing empty lines from the header
+        while registry.header.last().map(|line| line.trim().is_empty()).unwrap_or(false) {
+            registry.header.pop();
+        }
+
+        Ok(registry)
+    }
+
+    #[inline]
+    /// Read registry file
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::parse(String::from_utf8_lossy(&std::fs::read(path)?))
+    }
+
+    /// Atomically write registry to the file
+    ///
+    /// Registry is written to a temporary file next to the given one
+    /// and then renamed, so wine never sees partially written registry
+    pub fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+
+        let Some(file_name) = path.file_name() else {
+            anyhow::bail!("Wrong registry file path: {path:?}");
+        };
+
+        let temp = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+        std::fs::write(&temp, self.to_string())?;
+
+        if let Ok(metadata) = path.metadata() {
+            std::fs::set_permissions(&temp, metadata.permissions())?;
+        }
+
+        std::fs::rename(temp, path)?;
+
+        Ok(())
+    }
+
+    #[inline]
+    /// Get key with given path
+    pub fn get_key(&self, path: impl AsRef<str>) -> Option<&RegistryKey> {
+        self.keys.iter().find(|key| key.is(&path))
+    }
+
+    #[inline]
+    /// Get mutable key with given path
+    pub fn get_key_mut(&mut self, path: impl AsRef<str>) -> Option<&mut RegistryKey> {
+        self.keys.iter_mut().find(|key| key.is(&path))
+    }
+
+    /// Get mutable key with given path, creating it if needed
+    pub fn key_mut(&mut self, path: impl AsRef<str>) -> &mut RegistryKey {
+        let path = path.as_ref();
+
+        let index = match self.keys.iter().position(|key| key.is(path)) {
+            Some(index) => index,
+            None => {
+                self.keys.push(RegistryKey::new(path));
+
+                self.keys.len() - 1
+            }
+        };
+
+        &mut self.keys[index]
+    }
+
+    /// Insert given key, replacing the existing one with the same path
+    pub fn insert_key(&mut self, key: RegistryKey) {
+        match self.keys.iter_mut().find(|current| current.is(&key.path)) {
+            Some(current) => *current = key,
+            None => self.keys.push(key)
+        }
+    }
+
+    /// Remove key with given path
+    ///
+    /// Subkeys are not removed
+    pub fn remove_key(&mut self, path: impl AsRef<str>) -> Option<RegistryKey> {
+        let index = self.keys.iter().position(|key| key.is(&path))?;
+
+        Some(self.keys.remove(index))
+    }
+
+    #[inline]
+    /// Get value of the key with given path
+    pub fn get_value<'a>(&self, path: impl AsRef<str>, name: impl Into<Option<&'a str>>) -> Option<&RegistryValue> {
+        self.get_key(path)?.get(name)
+    }
+
+    #[inline]
+    /// Set value of the key with given path, creating the key if needed
+    pub fn set_value<'a>(&mut self, path: impl AsRef<str>, name: impl Into<Option<&'a str>>, value: RegistryValue) {
+        self.key_mut(path).set(name, value);
+    }
+
+    #[inline]
+    /// Remove value of the key with given path
+    pub fn remove_value<'a>(&mut self, path: impl AsRef<str>, name: impl Into<Option<&'a str>>) -> Option<RegistryValue> {
+        self.get_key_mut(path)?.remove(name)
+    }
+}
+
+impl std::fmt::Display for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.header {
+            writeln!(f, "{line}")?;
+        }
+
+        for key in &self.keys {
+            writeln!(f)?;
+            write!(f, "{key}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[inline]
+fn names_equal(a: Option<&str>, b: Option<&str>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (None, None) => true,
+
+        _ => false
+    }
+}
+
+/// Unescape string until the given unescaped delimiter
+///
+/// Returns unescaped string and the rest of the input after the delimiter
+fn unescape(value: &str, delimiter: char) -> anyhow::Result<(String, &str)> {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.char_indices().peekable();
+
+    while let Some((i, char)) = chars.next() {
+        if char == delimiter {
+            return Ok((result, &value[i + char.len_utf8()..]));
+        }
+
+        if char != '\\' {
+            result.push(char);
+
+            continue;
+        }
+
+        let Some((_, escaped)) = chars.next() else {
+            break;
+        };
+
+        match escaped {
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            'a' => result.push('\x07'),
+            'b' => result.push('\x08'),
+            'e' => result.push('\x1b'),
+            'f' => result.push('\x0c'),
+            'v' => result.push('\x0b'),
+
+            // Wine reads up to 3 octal digits
+            '0'..='7' => {
+                let mut code = escaped.to_digit(8).unwrap_or(0);
+
+                for _ in 0..2 {
+                    let Some(digit) = chars.peek().and_then(|(_, digit)| digit.to_digit(8)) else {
+                        break;
+                    };
+
+                    code = code * 8 + digit;
+
+                    chars.next();
+                }
+
+                result.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+
+            'x' => {
+                let mut code = String::new();
+
+                while let Some((_, digit)) = chars.peek() {
+                    if code.len() == 4 || !digit.is_ascii_hexdigit() {
+                        break;
+                    }
+
+                    code.push(*digit);
+
+                    chars.next();
+                }
+
+                let code = u32::from_str_radix(&code, 16)?;
+
+                result.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+
+            escaped => result.push(escaped)
+        }
+    }
+
+    anyhow::bail!("Unterminated registry string: expected '{delimiter}'")
+}
+
+/// Escape string using given delimiter
+fn escape(value: &str, delimiter: char) -> String {
+    let mut result = String::with_capacity(value.len());
+
+    for char in value.chars() {
+        match char {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+
+            char if char == delimiter => {
+                result.push('\\');
+                result.push(char);
+            }
+
+            // Wine reads up to 4 hex digits so we always pad the code
+            char if (char as u32) < 0x20 => result.push_str(&format!("\\x{:04x}", char as u32)),
+
+            char => result.push(char)
+        }
+    }
+
+    result
+}
+
+fn parse_hex(bytes: &str) -> anyhow::Result<Vec<u8>> {
+    bytes.split(',')
+        .map(str::trim)
+        .filter(|byte| !byte.is_empty())
+        .map(|byte| Ok(u8::from_str_radix(byte, 16)?))
+        .collect()
+}
+
+fn format_hex(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGISTRY: &str = r#"WINE REGISTRY Version 2
+;; All keys relative to \\User\\S-1-5-21-0-0-0-1000
+
+#arch=win64
+
+[Software\\Wine\\DllOverrides] 1700000000
+#time=1da1b2c3d4e5f60
+"dxgi"="native"
+"d3d11"="native,builtin"
+
+[Control Panel\\Desktop] 1700000001
+#time=1da1b2c3d4e5f61
+@="default"
+"ScreenSaveActive"=dword:00000001
+"Path"=str(2):"%SystemRoot%\\system32"
+"Binary"=hex:01,02,ff
+"Multi"=hex(7):41,00,00,00,42,00,00,00,00,00
+"Long"=hex(3):00,01,02,03,04,05,06,07,08,09,0a,0b,0c,0d,0e,0f,\
+  10,11,12,13
+"#;
+
+    #[test]
+    fn parse() -> anyhow::Result<()> {
+        let registry = Registry::parse(REGISTRY)?;
+
+        assert_eq!(registry.header, [
+            "WINE REGISTRY Version 2",
+            r";; All keys relative to \\User\\S-1-5-21-0-0-0-1000",
+            "",
+            "#arch=win64"
+        ]);
+
+        let key = registry.get_key(r"software\wine\dlloverrides").unwrap();
+
+        assert_eq!(key.path, r"Software\Wine\DllOverrides");
+        assert_eq!(key.timestamp, Some(1700000000));
+        assert_eq!(key.metadata, ["#time=1da1b2c3d4e5f60"]);
+
+        assert_eq!(key.get("DXGI").and_then(RegistryValue::as_str), Some("native"));
+
+        let key = registry.get_key(r"Control Panel\Desktop").unwrap();
+
+        assert_eq!(key.get(None), Some(&RegistryValue::String(String::from("default"))));
+        assert_eq!(key.get("ScreenSaveActive").and_then(RegistryValue::as_dword), Some(1));
+        assert_eq!(key.get("Path"), Some(&RegistryValue::TypedString(2, String::from(r"%SystemRoot%\system32"))));
+        assert_eq!(key.get("Binary"), Some(&RegistryValue::Binary(vec![1, 2, 255])));
+        assert_eq!(key.get("Multi"), Some(&RegistryValue::TypedBinary(7, vec![0x41, 0, 0, 0, 0x42, 0, 0, 0, 0, 0])));
+
+        // Continued line
+        assert_eq!(key.get("Long"), Some(&RegistryValue::TypedBinary(3, (0..20).collect())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> anyhow::Result<()> {
+        let registry = Registry::parse(REGISTRY)?;
+
+        assert_eq!(Registry::parse(registry.to_string())?, registry);
+        assert_eq!(Registry::parse(REGISTRY.replace('\n', "\r\n"))?, registry);
+
+        Ok(())
+    }
+
+    #[test]
+    fn escapes() -> anyhow::Result<()> {
+        let registry = Registry::parse("[Test]\n\"value\"=\"a\\\"b\\\\c\\nd\\x41\\x00e9\\101\\0\\1234\"\n")?;
+
+        // \1234 is the \123 octal code followed by 4
+        assert_eq!(registry.get_value("Test", "value").and_then(RegistryValue::as_str), Some("a\"b\\c\nd\u{41}\u{e9}A\0S4"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn escapes_round_trip() -> anyhow::Result<()> {
+        let mut registry = Registry::default();
+
+        let value = String::from("quote\" backslash\\ tab\t bell\x07 null\0 ]bracket unicode \u{4e2d}");
+
+        registry.set_value(r"Key]\Sub", "name\"", RegistryValue::String(value.clone()));
+        registry.set_value(r"Key]\Sub", None, RegistryValue::TypedString(2, value.clone()));
+
+        let parsed = Registry::parse(registry.to_string())?;
+
+        assert_eq!(parsed, registry);
+        assert_eq!(parsed.get_value(r"key]\sub", "NAME\"").and_then(RegistryValue::as_str), Some(value.as_str()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn values_round_trip() -> anyhow::Result<()> {
+        let values = [
+            RegistryValue::Dword(0),
+            RegistryValue::Dword(u32::MAX),
+            RegistryValue::Binary(vec![]),
+            RegistryValue::Binary(vec![0, 127, 255]),
+            RegistryValue::TypedBinary(0xb, vec![1, 2, 3, 4, 5, 6, 7, 8]),
+            RegistryValue::Raw(String::from("unknown:value"))
+        ];
+
+        for value in values {
+            assert_eq!(RegistryValue::parse(&value.to_string())?, value);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn modify() -> anyhow::Result<()> {
+        let mut registry = Registry::parse(REGISTRY)?;
+
+        registry.set_value(r"Software\Wine\DllOverrides", "dxgi", RegistryValue::String(String::from("builtin")));
+        registry.remove_value(r"Software\Wine\DllOverrides", "d3d11");
+        registry.set_value(r"Software\Wine\Drivers", "Graphics", RegistryValue::String(String::from("wayland")));
+
+        let registry = Registry::parse(registry.to_string())?;
+
+        assert_eq!(registry.get_value(r"Software\Wine\DllOverrides", "dxgi").and_then(RegistryValue::as_str), Some("builtin"));
+        assert_eq!(registry.get_value(r"Software\Wine\DllOverrides", "d3d11"), None);
+        assert_eq!(registry.get_value(r"Software\Wine\Drivers", "Graphics").and_then(RegistryValue::as_str), Some("wayland"));
+
+        // Timestamps of the existing keys are kept
+        assert_eq!(registry.get_key(r"Software\Wine\DllOverrides").and_then(|key| key.timestamp), Some(1700000000));
+        assert_eq!(registry.get_key(r"Software\Wine\Drivers").and_then(|key| key.timestamp), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+//! Per-game shader caches
+//!
+//! DXVK, VKD3D and Mesa caches are stored in `<cache_dir>/shaders/<runner>`
+//! folders, so every game and runner have their own caches which can be
+//! measured and cleared independently
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use crate::components::gc::get_size;
+
+/// File which stores game and dxvk versions the cache was made for
+const VERSION_FILE: &str = ".version";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ShaderCacheKind {
+    Dxvk,
+    Vkd3d,
+    Mesa
+}
+
+impl ShaderCacheKind {
+    #[inline]
+    pub fn list() -> &'static [Self] {
+        &[Self::Dxvk, Self::Vkd3d, Self::Mesa]
+    }
+
+    #[inline]
+    /// Get name of the cache's folder
+    pub fn folder_name(&self) -> &'static str {
+        match self {
+            Self::Dxvk  => "dxvk",
+            Self::Vkd3d => "vkd3d",
+            Self::Mesa  => "mesa"
+        }
+    }
+
+    #[inline]
+    /// Get environment variable which sets the cache's folder
+    pub fn env_var(&self) -> &'static str {
+        match self {
+            Self::Dxvk  => "DXVK_STATE_CACHE_PATH",
+            Self::Vkd3d => "VKD3D_SHADER_CACHE_PATH",
+            Self::Mesa  => "MESA_SHADER_CACHE_DIR"
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShaderCache {
+    /// Path to the cache folder of the runner
+    pub folder: PathBuf
+}
+
+impl ShaderCache {
+    #[inline]
+    /// Get shader cache of the runner with given name
+    ///
+    /// `cache_dir` should be the game's `consts::cache_dir()`
+    pub fn new(cache_dir: impl AsRef<Path>, runner: impl AsRef<str>) -> Self {
+        Self {
+            folder: shaders_folder(cache_dir).join(runner.as_ref())
+        }
+    }
+
+    #[inline]
+    /// Get folder of the cache with given kind
+    pub fn kind_folder(&self, kind: ShaderCacheKind) -> PathBuf {
+        self.folder.join(kind.folder_name())
+    }
+
+    /// Create caches folders
+    pub fn prepare(&self) -> anyhow::Result<()> {
+        for kind in ShaderCacheKind::list() {
+            std::fs::create_dir_all(self.kind_folder(*kind))?;
+        }
+
+        Ok(())
+    }
+
+    /// Get environment variables which point caches to their folders
+    pub fn get_env_vars(&self) -> HashMap<&str, String> {
+        ShaderCacheKind::list()
+            .iter()
+            .map(|kind| (kind.env_var(), self.kind_folder(*kind).to_string_lossy().to_string()))
+            .collect()
+    }
+
+    /// Get sizes of the caches in bytes
+    pub fn sizes(&self) -> HashMap<ShaderCacheKind, u64> {
+        ShaderCacheKind::list()
+            .iter()
+            .map(|kind| (*kind, get_size(self.kind_folder(*kind)).unwrap_or(0)))
+            .collect()
+    }
+
+    #[inline]
+    /// Get total size of the caches in bytes
+    pub fn size(&self) -> u64 {
+        self.sizes().values().sum()
+    }
+
+    /// Remove all the caches
+    pub fn clear(&self) -> anyhow::Result<()> {
+        if self.folder.exists() {
+            std::fs::remove_dir_all(&self.folder)?;
+        }
+
+        Ok(())
+    }
+
+    /// Clear caches if they were made for another game or dxvk version
+    ///
+    /// Returns `true` if the caches were cleared
+    pub fn validate(&self, game_version: impl AsRef<str>, dxvk_version: Option<&str>) -> anyhow::Result<bool> {
+        let version = format!("{}\n{}", game_version.as_ref(), dxvk_version.unwrap_or_default());
+        let version_file = self.folder.join(VERSION_FILE);
+
+        let cleared = match std::fs::read_to_string(&version_file) {
+            Ok(current) if current == version => false,
+
+            // Don't clear caches which were made before versions tracking
+            Err(_) if !self.folder.exists() => false,
+
+            _ => {
+                self.clear()?;
+
+                true
+            }
+        };
+
+        std::fs::create_dir_all(&self.folder)?;
+        std::fs::write(version_file, version)?;
+
+        Ok(cleared)
+    }
+}
+
+#[inline]
+/// Get folder which stores shader caches of all the runners
+pub fn shaders_folder(cache_dir: impl AsRef<Path>) -> PathBuf {
+    cache_dir.as_ref().join("shaders")
+}
+
+/// List shader caches of all the runners
+pub fn list(cache_dir: impl AsRef<Path>) -> anyhow::Result<Vec<ShaderCache>> {
+    let folder = shaders_folder(cache_dir);
+
+    if !folder.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut caches = Vec::new();
+
+    for entry in std::fs::read_dir(folder)?.flatten() {
+        if entry.path().is_dir() {
+            caches.push(ShaderCache {
+                folder: entry.path()
+            });
+        }
+    }
+
+    Ok(caches)
+}
+
+/// Remove shader caches of all the runners
+pub fn purge(cache_dir: impl AsRef<Path>) -> anyhow::Result<()> {
+    let folder = shaders_folder(cache_dir);
+
+    if folder.exists() {
+        std::fs::remove_dir_all(folder)?;
+    }
+
+    Ok(())
+}